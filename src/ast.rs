@@ -1,11 +1,21 @@
 // https://docs.docker.com/reference/dockerfile/#overview
 
-use std::collections::BTreeMap;
 use std::fmt;
+use std::time::Duration;
 
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::identity::Identity;
+use crate::image::ImageRef;
+use crate::mount::Mount;
+use crate::port::PortSpec;
+use crate::quoter::Quoter;
+use crate::signal::Signal;
+use crate::symbols::chars::BACKSLASH;
+use crate::word::Word;
+use crate::workdir::WorkdirPath;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 /// This enum represents available instructions in a Dockerfile and their associated data.
 pub enum Instruction {
@@ -15,52 +25,64 @@ pub enum Instruction {
     ///
     /// ```
     /// use dockerfile_parser_rs::Instruction;
+    /// use dockerfile_parser_rs::Source;
+    /// use dockerfile_parser_rs::Word;
     ///
     /// let add = Instruction::Add {
     ///     checksum: None,
     ///     chown: None,
     ///     chmod: None,
-    ///     link: None,
-    ///     sources: Vec::from([String::from("source1"), String::from("source2")]),
-    ///     destination: String::from("/destination"),
+    ///     exclude: Vec::new(),
+    ///     keep_git_dir: false,
+    ///     link: false,
+    ///     unpack: None,
+    ///     sources: Vec::from([
+    ///         Source::Path(Word::from("source1")),
+    ///         Source::Path(Word::from("source2")),
+    ///     ]),
+    ///     destination: Word::from("/destination"),
     /// };
     /// ```
     Add {
-        checksum: Option<String>,
-        chown: Option<String>,
-        chmod: Option<String>,
-        link: Option<String>,
-        sources: Vec<String>,
-        destination: String,
+        checksum: Option<Word>,
+        chown: Option<Word>,
+        chmod: Option<Word>,
+        exclude: Vec<Word>,
+        keep_git_dir: bool,
+        link: bool,
+        /// Whether archives are unpacked, which depends on the source if not given.
+        unpack: Option<bool>,
+        sources: Vec<Source>,
+        destination: Word,
     },
     /// ARG Dockerfile instruction.
     ///
     /// ### Example
     ///
     /// ```
-    /// use std::collections::BTreeMap;
-    ///
     /// use dockerfile_parser_rs::Instruction;
+    /// use dockerfile_parser_rs::Word;
     ///
-    /// let arg = Instruction::Arg(BTreeMap::from([
-    ///     (String::from("ARG1"), Some(String::from("value1"))),
+    /// let arg = Instruction::Arg(vec![
+    ///     (String::from("ARG1"), Some(Word::from("value1"))),
     ///     (String::from("ARG2"), None),
-    /// ]));
+    /// ]);
     /// ```
-    Arg(BTreeMap<String, Option<String>>),
+    Arg(Vec<(String, Option<Word>)>),
     /// CMD Dockerfile instruction.
     ///
     /// ### Example
     ///
     /// ```
+    /// use dockerfile_parser_rs::Command;
     /// use dockerfile_parser_rs::Instruction;
     ///
-    /// let cmd = Instruction::Cmd(Vec::from([
+    /// let cmd = Instruction::Cmd(Command::Exec(Vec::from([
     ///     String::from("echo"),
     ///     String::from("Hello, World!"),
-    /// ]));
+    /// ])));
     /// ```
-    Cmd(Vec<String>),
+    Cmd(Command),
     /// A comment.
     ///
     /// ### Example
@@ -76,24 +98,34 @@ pub enum Instruction {
     /// ### Example
     ///
     /// ```
+    /// use dockerfile_parser_rs::ImageRef;
     /// use dockerfile_parser_rs::Instruction;
+    /// use dockerfile_parser_rs::Source;
+    /// use dockerfile_parser_rs::Word;
     ///
     /// let copy = Instruction::Copy {
-    ///     from: Some(String::from("builder")),
+    ///     from: Some(ImageRef::Stage(String::from("builder"))),
     ///     chown: None,
     ///     chmod: None,
-    ///     link: None,
-    ///     sources: Vec::from([String::from("source1"), String::from("source2")]),
-    ///     destination: String::from("/destination"),
+    ///     exclude: Vec::new(),
+    ///     link: false,
+    ///     parents: false,
+    ///     sources: Vec::from([
+    ///         Source::Path(Word::from("source1")),
+    ///         Source::Path(Word::from("source2")),
+    ///     ]),
+    ///     destination: Word::from("/destination"),
     /// };
     /// ```
     Copy {
-        from: Option<String>,
-        chown: Option<String>,
-        chmod: Option<String>,
-        link: Option<String>,
-        sources: Vec<String>,
-        destination: String,
+        from: Option<ImageRef>,
+        chown: Option<Word>,
+        chmod: Option<Word>,
+        exclude: Vec<Word>,
+        link: bool,
+        parents: bool,
+        sources: Vec<Source>,
+        destination: Word,
     },
     /// An empty line.
     ///
@@ -110,26 +142,28 @@ pub enum Instruction {
     /// ### Example
     ///
     /// ```
+    /// use dockerfile_parser_rs::Command;
     /// use dockerfile_parser_rs::Instruction;
     ///
-    /// let entrypoint = Instruction::Entrypoint(Vec::from([String::from("entrypoint.sh")]));
+    /// let entrypoint = Instruction::Entrypoint(Command::Exec(Vec::from([String::from(
+    ///     "entrypoint.sh",
+    /// )])));
     /// ```
-    Entrypoint(Vec<String>),
+    Entrypoint(Command),
     /// ENV Dockerfile instruction.
     ///
     /// ### Example
     ///
     /// ```
-    /// use std::collections::BTreeMap;
-    ///
     /// use dockerfile_parser_rs::Instruction;
+    /// use dockerfile_parser_rs::Word;
     ///
-    /// let env = Instruction::Env(BTreeMap::from([
-    ///     (String::from("ENV1"), String::from("value1")),
-    ///     (String::from("ENV2"), String::from("value2")),
-    /// ]));
+    /// let env = Instruction::Env(vec![
+    ///     (Word::from("ENV1"), Word::from("value1")),
+    ///     (Word::from("ENV2"), Word::from("value2")),
+    /// ]);
     /// ```
-    Env(BTreeMap<String, String>),
+    Env(Vec<(Word, Word)>),
     /// EXPOSE Dockerfile instruction.
     ///
     /// ### Example
@@ -138,68 +172,126 @@ pub enum Instruction {
     /// use dockerfile_parser_rs::Instruction;
     ///
     /// let expose = Instruction::Expose {
-    ///     ports: Vec::from([String::from("8080")]),
+    ///     ports: Vec::from(["8080/tcp".parse().unwrap()]),
     /// };
     /// ```
-    Expose { ports: Vec<String> },
+    Expose { ports: Vec<PortSpec> },
+    /// HEALTHCHECK Dockerfile instruction.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use dockerfile_parser_rs::Command;
+    /// use dockerfile_parser_rs::Healthcheck;
+    /// use dockerfile_parser_rs::Instruction;
+    ///
+    /// let healthcheck = Instruction::Healthcheck(Healthcheck::Cmd {
+    ///     interval: Some(Duration::from_secs(30)),
+    ///     timeout: None,
+    ///     start_period: None,
+    ///     start_interval: None,
+    ///     retries: Some(3),
+    ///     command: Command::Shell(String::from("healthcheck.sh")),
+    /// });
+    /// ```
+    Healthcheck(Healthcheck),
     /// FROM Dockerfile instruction.
     ///
     /// ### Example
     ///
     /// ```
     /// use dockerfile_parser_rs::Instruction;
+    /// use dockerfile_parser_rs::Word;
     ///
     /// let from = Instruction::From {
-    ///     platform: Some(String::from("linux/amd64")),
-    ///     image: String::from("docker.io/library/fedora:latest"),
+    ///     platform: Some(Word::from("linux/amd64")),
+    ///     image: "docker.io/library/fedora:latest".parse().unwrap(),
     ///     alias: Some(String::from("builder")),
     /// };
     /// ```
     From {
-        platform: Option<String>,
-        image: String,
+        platform: Option<Word>,
+        image: ImageRef,
         alias: Option<String>,
     },
+    /// A line that could not be parsed, holding its original text.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use dockerfile_parser_rs::Instruction;
+    ///
+    /// let invalid = Instruction::Invalid(String::from("MAKE love"));
+    /// ```
+    Invalid(String),
     /// LABEL Dockerfile instruction.
     ///
     /// ### Example
     ///
     /// ```
-    /// use std::collections::BTreeMap;
+    /// use dockerfile_parser_rs::Instruction;
+    /// use dockerfile_parser_rs::Word;
     ///
+    /// let label = Instruction::Label(vec![
+    ///     (Word::from("version"), Word::from("1.0")),
+    ///     (Word::from("maintainer"), Word::from("John Doe")),
+    /// ]);
+    /// ```
+    Label(Vec<(Word, Word)>),
+    /// MAINTAINER Dockerfile instruction (deprecated).
+    ///
+    /// ### Example
+    ///
+    /// ```
     /// use dockerfile_parser_rs::Instruction;
     ///
-    /// let label = Instruction::Label(BTreeMap::from([
-    ///     (String::from("version"), String::from("1.0")),
-    ///     (String::from("maintainer"), String::from("John Doe")),
-    /// ]));
+    /// let maintainer = Instruction::Maintainer {
+    ///     name: String::from("John Doe <john.doe@example.com>"),
+    /// };
     /// ```
-    Label(BTreeMap<String, String>),
+    Maintainer { name: String },
+    /// ONBUILD Dockerfile instruction.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use dockerfile_parser_rs::Instruction;
+    ///
+    /// let onbuild = Instruction::Onbuild(Box::new(Instruction::Workdir {
+    ///     path: "/app".parse().unwrap(),
+    /// }));
+    /// ```
+    Onbuild(Box<Self>),
     /// RUN Dockerfile instruction.
     ///
     /// ### Example
     ///
     /// ```
+    /// use dockerfile_parser_rs::Command;
+    /// use dockerfile_parser_rs::Heredoc;
     /// use dockerfile_parser_rs::Instruction;
     ///
     /// let run = Instruction::Run {
-    ///     mount: None,
+    ///     mounts: Vec::new(),
     ///     network: None,
     ///     security: None,
-    ///     command: Vec::from([String::from("<<EOF")]),
-    ///     heredoc: Some(Vec::from([
-    ///         String::from("dnf upgrade -y"),
-    ///         String::from("dnf install -y rustup"),
-    ///         String::from("EOF"),
-    ///     ])),
+    ///     command: Command::Shell(String::from("<<EOF")),
+    ///     heredocs: Vec::from([Heredoc {
+    ///         delimiter: String::from("EOF"),
+    ///         strip_tabs: false,
+    ///         quote: None,
+    ///         body: String::from("dnf upgrade -y\ndnf install -y rustup\n"),
+    ///     }]),
     /// };
     /// ```
     Run {
-        mount: Option<String>,
+        mounts: Vec<Mount>,
         network: Option<String>,
         security: Option<String>,
-        command: Vec<String>,
-        heredoc: Option<Vec<String>>,
+        command: Command,
+        heredocs: Vec<Heredoc>,
     },
     /// SHELL Dockerfile instruction.
     ///
@@ -219,35 +311,40 @@ pub enum Instruction {
     /// use dockerfile_parser_rs::Instruction;
     ///
     /// let stopsignal = Instruction::Stopsignal {
-    ///     signal: String::from("SIGTERM"),
+    ///     signal: "SIGTERM".parse().unwrap(),
     /// };
     /// ```
-    Stopsignal { signal: String },
+    Stopsignal { signal: Signal },
     /// USER Dockerfile instruction.
     ///
     /// ### Example
     ///
     /// ```
+    /// use dockerfile_parser_rs::Identity;
     /// use dockerfile_parser_rs::Instruction;
     ///
     /// let user = Instruction::User {
-    ///     user: String::from("1001"),
+    ///     user: Identity::Id(1001),
     ///     group: None,
     /// };
     /// ```
-    User { user: String, group: Option<String> },
+    User {
+        user: Identity,
+        group: Option<Identity>,
+    },
     /// VOLUME Dockerfile instruction.
     ///
     /// ### Example
     ///
     /// ```
     /// use dockerfile_parser_rs::Instruction;
+    /// use dockerfile_parser_rs::Word;
     ///
     /// let volume = Instruction::Volume {
-    ///     mounts: Vec::from([String::from("/data")]),
+    ///     mounts: Vec::from([Word::from("/data")]),
     /// };
     /// ```
-    Volume { mounts: Vec<String> },
+    Volume { mounts: Vec<Word> },
     /// WORKDIR Dockerfile instruction.
     ///
     /// ### Example
@@ -256,67 +353,288 @@ pub enum Instruction {
     /// use dockerfile_parser_rs::Instruction;
     ///
     /// let workdir = Instruction::Workdir {
-    ///     path: String::from("/app"),
+    ///     path: "/app".parse().unwrap(),
     /// };
     /// ```
-    Workdir { path: String },
+    Workdir { path: WorkdirPath },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// This enum represents the two forms of the HEALTHCHECK instruction.
+pub enum Healthcheck {
+    /// Disable any healthcheck inherited from the base image.
+    None,
+    /// Check the container health by running a command inside of it.
+    Cmd {
+        interval: Option<Duration>,
+        timeout: Option<Duration>,
+        start_period: Option<Duration>,
+        start_interval: Option<Duration>,
+        retries: Option<u32>,
+        command: Command,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// This enum represents the command of the CMD, ENTRYPOINT, RUN and HEALTHCHECK instructions.
+///
+/// ### Example
+///
+/// ```
+/// use dockerfile_parser_rs::Command;
+///
+/// let shell = Command::Shell(String::from("echo $HOME"));
+/// let exec = Command::Exec(Vec::from([String::from("echo"), String::from("$HOME")]));
+///
+/// assert_eq!(shell.to_string(), "echo $HOME");
+/// assert_eq!(exec.to_string(), "[\"echo\", \"$HOME\"]");
+/// ```
+pub enum Command {
+    /// The command is run by the shell, exactly as written.
+    Shell(String),
+    /// The command is run directly, without a shell.
+    Exec(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// This enum represents a source of the COPY and ADD instructions.
+///
+/// ### Example
+///
+/// ```
+/// use dockerfile_parser_rs::Heredoc;
+/// use dockerfile_parser_rs::Source;
+/// use dockerfile_parser_rs::Word;
+///
+/// let path = Source::Path(Word::from("requirements.txt"));
+/// let inline = Source::Heredoc(Heredoc {
+///     delimiter: String::from("EOF"),
+///     strip_tabs: false,
+///     quote: None,
+///     body: String::from("numpy\n"),
+/// });
+///
+/// assert_eq!(path.to_string(), "requirements.txt");
+/// assert_eq!(inline.to_string(), "<<EOF");
+/// ```
+pub enum Source {
+    /// A path or URL of the build context.
+    Path(Word),
+    /// A file created inline from a here-document.
+    Heredoc(Heredoc),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// This struct represents a here-document attached to an instruction.
+///
+/// The command of the instruction keeps the redirection as written, e.g. `cat <<EOF > /file`, and
+/// the body follows the instruction line up to the line with the delimiter.
+///
+/// ### Example
+///
+/// ```
+/// use dockerfile_parser_rs::Heredoc;
+///
+/// let heredoc = Heredoc {
+///     delimiter: String::from("EOT"),
+///     strip_tabs: true,
+///     quote: Some('\''),
+///     body: String::from("\tcat $HOME\n"),
+/// };
+///
+/// assert_eq!(heredoc.to_string(), "\tcat $HOME\nEOT");
+/// assert_eq!(heredoc.content(), "cat $HOME\n");
+/// ```
+pub struct Heredoc {
+    /// The word that terminates the here-document, without quotes.
+    pub delimiter: String,
+    /// Whether leading tabs are removed from the body, as with `<<-EOF`.
+    pub strip_tabs: bool,
+    /// The quote around the delimiter as written, if any, e.g. `'` for `<<'EOF'`.
+    ///
+    /// Variables in the body are not expanded if the delimiter is quoted.
+    pub quote: Option<char>,
+    /// The lines of the body exactly as written, each followed by a line break.
+    pub body: String,
+}
+
+impl Heredoc {
+    /// Return whether variables in the body are expanded, which is the case for an unquoted
+    /// delimiter.
+    pub const fn expands(&self) -> bool {
+        self.quote.is_none()
+    }
+
+    /// Return the redirection that opens the here-document as written, e.g. `<<-EOF` or
+    /// `<<'EOF'`.
+    pub fn marker(&self) -> String {
+        let strip_tabs = if self.strip_tabs { "-" } else { "" };
+        self.quote.map_or_else(
+            || format!("<<{strip_tabs}{}", self.delimiter),
+            |quote| format!("<<{strip_tabs}{quote}{}{quote}", self.delimiter),
+        )
+    }
+
+    /// Return the body with leading tabs removed if the here-document asks for it.
+    pub fn content(&self) -> String {
+        if !self.strip_tabs {
+            return self.body.clone();
+        }
+        self.body
+            .split_inclusive('\n')
+            .map(|line| line.trim_start_matches('\t'))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// This struct displays an instruction using a custom escape character.
+///
+/// The escape character is used for escaped quotes and literal dollar signs. An instruction is
+/// written on a single line, followed by the bodies of its here-documents.
+pub struct EscapedInstruction<'a> {
+    instruction: &'a Instruction,
+    escape: char,
+}
+
+impl fmt::Display for EscapedInstruction<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.instruction.write_to(f, self.escape)
+    }
 }
 
 impl fmt::Display for Instruction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.with_escape(BACKSLASH))
+    }
+}
+
+impl Instruction {
+    /// Return a value that displays the instruction using the given escape character.
+    pub const fn with_escape(&self, escape: char) -> EscapedInstruction<'_> {
+        EscapedInstruction {
+            instruction: self,
+            escape,
+        }
+    }
+
+    /// Return the formatted options of the instruction.
+    fn options(&self, escape: char) -> Vec<String> {
         match self {
             Self::Add {
                 checksum,
                 chown,
                 chmod,
+                exclude,
+                keep_git_dir,
                 link,
-                sources,
-                destination,
+                unpack,
+                ..
             } => {
-                let options = vec![
-                    helpers::format_instruction_option("checksum", checksum.as_ref()),
-                    helpers::format_instruction_option("chown", chown.as_ref()),
-                    helpers::format_instruction_option("chmod", chmod.as_ref()),
-                    helpers::format_instruction_option("link", link.as_ref()),
+                let mut options = vec![
+                    helpers::format_word_option("checksum", checksum.as_ref(), escape),
+                    helpers::format_word_option("chown", chown.as_ref(), escape),
+                    helpers::format_word_option("chmod", chmod.as_ref(), escape),
                 ];
-                let prefix = helpers::format_options_string(&options);
-                write!(f, "ADD {prefix}{} {destination}", sources.join(" "))
+                options.extend(
+                    exclude
+                        .iter()
+                        .map(|e| helpers::format_word_option("exclude", Some(e), escape)),
+                );
+                options.extend([
+                    helpers::format_flag("keep-git-dir", *keep_git_dir),
+                    helpers::format_flag("link", *link),
+                    helpers::format_instruction_option(
+                        "unpack",
+                        unpack.map(|u| u.to_string()).as_ref(),
+                    ),
+                ]);
+                options
             }
-            Self::Arg(args) => write!(f, "ARG {}", helpers::format_optional_btree_map(args)),
-            Self::Cmd(cmd) => write!(f, "CMD {cmd:?}"),
-            Self::Comment(comment) => write!(f, "{comment}"),
             Self::Copy {
                 from,
                 chown,
                 chmod,
+                exclude,
                 link,
-                sources,
-                destination,
+                parents,
+                ..
             } => {
-                let options = vec![
-                    helpers::format_instruction_option("from", from.as_ref()),
-                    helpers::format_instruction_option("chown", chown.as_ref()),
-                    helpers::format_instruction_option("chmod", chmod.as_ref()),
-                    helpers::format_instruction_option("link", link.as_ref()),
+                let mut options = vec![
+                    helpers::format_instruction_option(
+                        "from",
+                        from.as_ref().map(ToString::to_string).as_ref(),
+                    ),
+                    helpers::format_word_option("chown", chown.as_ref(), escape),
+                    helpers::format_word_option("chmod", chmod.as_ref(), escape),
                 ];
-                let prefix = helpers::format_options_string(&options);
-                write!(f, "COPY {prefix}{} {destination}", sources.join(" "))
+                options.extend(
+                    exclude
+                        .iter()
+                        .map(|e| helpers::format_word_option("exclude", Some(e), escape)),
+                );
+                options.extend([
+                    helpers::format_flag("link", *link),
+                    helpers::format_flag("parents", *parents),
+                ]);
+                options
             }
-            Self::Empty {} => write!(f, ""),
-            Self::Entrypoint(entrypoint) => write!(f, "ENTRYPOINT {entrypoint:?}"),
-            Self::Env(env) => write!(f, "ENV {}", helpers::format_btree_map(env)),
-            Self::Expose { ports } => write!(f, "EXPOSE {}", ports.join(" ")),
-            Self::From {
-                platform,
-                image,
-                alias,
-            } => {
-                let options = vec![helpers::format_instruction_option(
+            Self::From { platform, .. } => {
+                vec![helpers::format_word_option(
                     "platform",
                     platform.as_ref(),
-                )];
-                let prefix = helpers::format_options_string(&options);
+                    escape,
+                )]
+            }
+            Self::Run {
+                mounts,
+                network,
+                security,
+                ..
+            } => {
+                let mut options: Vec<String> = mounts.iter().map(helpers::format_mount).collect();
+                options.extend([
+                    helpers::format_instruction_option("network", network.as_ref()),
+                    helpers::format_instruction_option("security", security.as_ref()),
+                ]);
+                options
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn write_to(&self, f: &mut impl fmt::Write, escape: char) -> fmt::Result {
+        let prefix = helpers::format_options_string(&self.options(escape));
 
+        match self {
+            Self::Add {
+                sources,
+                destination,
+                ..
+            } => {
+                write!(f, "ADD {prefix}")?;
+                helpers::write_sources(f, sources, destination, escape)
+            }
+            Self::Arg(args) => write!(f, "ARG {}", helpers::format_optional_pairs(args, escape)),
+            Self::Cmd(cmd) => write!(f, "CMD {cmd}"),
+            Self::Comment(comment) => write!(f, "{comment}"),
+            Self::Copy {
+                sources,
+                destination,
+                ..
+            } => {
+                write!(f, "COPY {prefix}")?;
+                helpers::write_sources(f, sources, destination, escape)
+            }
+            Self::Empty {} => write!(f, ""),
+            Self::Entrypoint(entrypoint) => write!(f, "ENTRYPOINT {entrypoint}"),
+            Self::Env(env) => write!(f, "ENV {}", helpers::format_pairs(env, escape)),
+            Self::Expose { ports } => {
+                let ports: Vec<String> = ports.iter().map(ToString::to_string).collect();
+                write!(f, "EXPOSE {}", ports.join(" "))
+            }
+            Self::Healthcheck(healthcheck) => write!(f, "HEALTHCHECK {healthcheck}"),
+            Self::From { image, alias, .. } => {
                 let mut line = format!("FROM {prefix}{image}");
                 if let Some(alias) = alias {
                     line.push_str(" AS ");
@@ -324,54 +642,180 @@ impl fmt::Display for Instruction {
                 }
                 write!(f, "{line}")
             }
-            Self::Label(labels) => write!(f, "LABEL {}", helpers::format_btree_map(labels)),
+            Self::Invalid(raw) => write!(f, "{raw}"),
+            Self::Label(labels) => write!(f, "LABEL {}", helpers::format_pairs(labels, escape)),
+            Self::Maintainer { name } => write!(f, "MAINTAINER {name}"),
+            Self::Onbuild(instruction) => {
+                write!(f, "ONBUILD ")?;
+                instruction.write_to(f, escape)
+            }
             Self::Run {
-                mount,
-                network,
-                security,
-                command,
-                heredoc,
+                command, heredocs, ..
             } => {
-                let options = vec![
-                    helpers::format_instruction_option("mount", mount.as_ref()),
-                    helpers::format_instruction_option("network", network.as_ref()),
-                    helpers::format_instruction_option("security", security.as_ref()),
-                ];
-                let prefix = helpers::format_options_string(&options);
-                match heredoc {
-                    Some(heredoc) => write!(
-                        f,
-                        "RUN {prefix}{}\n{}",
-                        command.join(" "),
-                        heredoc.join("\n")
-                    ),
-                    None => write!(f, "RUN {prefix}{}", command.join(" ")),
-                }
+                write!(f, "RUN {prefix}{command}")?;
+                helpers::write_heredocs(f, heredocs)
             }
-            Self::Shell(shell) => write!(f, "SHELL {shell:?}"),
+            Self::Shell(shell) => write!(f, "SHELL {}", helpers::format_json_array(shell)),
             Self::Stopsignal { signal } => write!(f, "STOPSIGNAL {signal}"),
             Self::User { user, group } => match group {
                 Some(group) => write!(f, "USER {user}:{group}"),
                 None => write!(f, "USER {user}"),
             },
-            Self::Volume { mounts } => write!(f, "VOLUME {mounts:?}"),
-            Self::Workdir { path } => write!(f, "WORKDIR {path}"),
+            Self::Volume { mounts } => write!(f, "VOLUME {}", helpers::format_json_array(mounts)),
+            Self::Workdir { path } => match path {
+                // the variables are kept as written
+                WorkdirPath::Variable(path) => write!(f, "WORKDIR {path}"),
+                _ => write!(f, "WORKDIR {}", Word::from(path.as_str()).quote(escape)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Healthcheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "NONE"),
+            Self::Cmd {
+                interval,
+                timeout,
+                start_period,
+                start_interval,
+                retries,
+                command,
+            } => {
+                let format_duration = |d: &Option<Duration>| d.map(helpers::format_duration);
+                let options = vec![
+                    helpers::format_instruction_option(
+                        "interval",
+                        format_duration(interval).as_ref(),
+                    ),
+                    helpers::format_instruction_option(
+                        "timeout",
+                        format_duration(timeout).as_ref(),
+                    ),
+                    helpers::format_instruction_option(
+                        "start-period",
+                        format_duration(start_period).as_ref(),
+                    ),
+                    helpers::format_instruction_option(
+                        "start-interval",
+                        format_duration(start_interval).as_ref(),
+                    ),
+                    helpers::format_instruction_option(
+                        "retries",
+                        retries.map(|r| r.to_string()).as_ref(),
+                    ),
+                ];
+                let prefix = helpers::format_options_string(&options);
+                write!(f, "{prefix}CMD {command}")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Path(path) => write!(f, "{path}"),
+            Self::Heredoc(heredoc) => write!(f, "{}", heredoc.marker()),
+        }
+    }
+}
+
+impl fmt::Display for Heredoc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.body, self.delimiter)
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Shell(command) => write!(f, "{command}"),
+            Self::Exec(arguments) => write!(f, "{}", helpers::format_json_array(arguments)),
         }
     }
 }
 
 mod helpers {
-    use std::collections::BTreeMap;
+    use std::fmt::Display;
+    use std::fmt::Write;
+    use std::time::Duration;
 
+    use super::Heredoc;
+    use super::Source;
+    use crate::mount::Mount;
     use crate::quoter::Quoter;
+    use crate::symbols::chars::DOUBLE_QUOTE;
+    use crate::symbols::chars::SINGLE_QUOTE;
+    use crate::word::Word;
+
+    /// Write the sources and the destination, followed by the bodies of inline sources.
+    pub fn write_sources(
+        f: &mut impl Write,
+        sources: &[Source],
+        destination: &Word,
+        escape: char,
+    ) -> std::fmt::Result {
+        for source in sources {
+            match source {
+                Source::Path(path) => write!(f, "{} ", path.quote(escape))?,
+                Source::Heredoc(heredoc) => write!(f, "{} ", heredoc.marker())?,
+            }
+        }
+        write!(f, "{}", destination.quote(escape))?;
+        write_heredocs(
+            f,
+            sources.iter().filter_map(|source| match source {
+                Source::Heredoc(heredoc) => Some(heredoc),
+                Source::Path(_) => None,
+            }),
+        )
+    }
 
-    pub fn format_instruction_option(key: &str, value: Option<&String>) -> String {
+    /// Write the bodies of the here-documents after the instruction line.
+    pub fn write_heredocs<'a>(
+        f: &mut impl Write,
+        heredocs: impl IntoIterator<Item = &'a Heredoc>,
+    ) -> std::fmt::Result {
+        for heredoc in heredocs {
+            write!(f, "\n{heredoc}")?;
+        }
+        Ok(())
+    }
+
+    pub fn format_instruction_option(key: &str, value: Option<&impl Display>) -> String {
         value
             .as_ref()
             .map(|v| format!("--{key}={v}"))
             .unwrap_or_default()
     }
 
+    pub fn format_word_option(key: &str, value: Option<&Word>, escape: char) -> String {
+        value
+            .map(|v| format!("--{key}={}", v.quote(escape)))
+            .unwrap_or_default()
+    }
+
+    /// Format a mount option, in single quotes if it has quoted fields so that they are kept.
+    pub fn format_mount(mount: &Mount) -> String {
+        let mount = mount.to_string();
+        if mount.contains(DOUBLE_QUOTE) {
+            // a single quote cannot be escaped in single quotes, so it goes in double quotes
+            format!("--mount='{}'", mount.replace(SINGLE_QUOTE, r#"'"'"'"#))
+        } else {
+            format!("--mount={mount}")
+        }
+    }
+
+    pub fn format_flag(key: &str, value: bool) -> String {
+        if value {
+            format!("--{key}")
+        } else {
+            String::new()
+        }
+    }
+
     pub fn format_options_string(options: &[String]) -> String {
         let result = options
             .iter()
@@ -388,18 +832,57 @@ mod helpers {
         }
     }
 
-    pub fn format_btree_map(pairs: &BTreeMap<String, String>) -> String {
+    pub fn format_duration(duration: Duration) -> String {
+        if duration.is_zero() {
+            return String::from("0s");
+        }
+
+        let nanos = duration.as_nanos();
+        let units = [
+            ("h", 3_600_000_000_000),
+            ("m", 60_000_000_000),
+            ("s", 1_000_000_000),
+            ("ms", 1_000_000),
+            ("us", 1_000),
+            ("ns", 1),
+        ];
+
+        let mut result = String::new();
+        let mut remainder = nanos;
+        for (unit, size) in units {
+            let count = remainder / size;
+            if count > 0 {
+                let _ = write!(result, "{count}{unit}");
+                remainder %= size;
+            }
+        }
+        result
+    }
+
+    pub fn format_pairs(pairs: &[(Word, Word)], escape: char) -> String {
         pairs
             .iter()
-            .map(|(key, value)| format!("{key}={}", value.enquote()))
+            .map(|(key, value)| format!("{}={}", key.quote(escape), value.enquote(escape)))
             .collect::<Vec<String>>()
             .join(" ")
     }
 
-    pub fn format_optional_btree_map(pairs: &BTreeMap<String, Option<String>>) -> String {
+    pub fn format_json_array(values: &[impl AsRef<str>]) -> String {
+        let values = values
+            .iter()
+            .map(|value| serde_json::to_string(value.as_ref()).unwrap_or_default())
+            .collect::<Vec<String>>()
+            .join(", ");
+        format!("[{values}]")
+    }
+
+    pub fn format_optional_pairs(pairs: &[(String, Option<Word>)], escape: char) -> String {
         pairs
             .iter()
-            .map(|(k, v)| v.as_ref().map_or_else(|| k.clone(), |v| format!("{k}={v}")))
+            .map(|(k, v)| {
+                v.as_ref()
+                    .map_or_else(|| k.clone(), |v| format!("{k}={}", v.quote(escape)))
+            })
             .collect::<Vec<_>>()
             .join(" ")
     }
@@ -407,7 +890,14 @@ mod helpers {
 
 #[cfg(test)]
 mod tests {
+    use std::str::FromStr;
+
     use super::*;
+    use crate::file::Dockerfile;
+    use crate::parser::parse_instruction;
+    use crate::symbols::chars::BACKTICK;
+    use crate::symbols::chars::DOUBLE_QUOTE;
+    use crate::symbols::chars::SINGLE_QUOTE;
 
     #[test]
     fn test_display_instruction_add() {
@@ -415,9 +905,15 @@ mod tests {
             checksum: None,
             chown: None,
             chmod: None,
-            link: None,
-            sources: vec![String::from("source1"), String::from("source2")],
-            destination: String::from("/destination"),
+            exclude: Vec::new(),
+            keep_git_dir: false,
+            link: false,
+            unpack: None,
+            sources: vec![
+                Source::Path(Word::from("source1")),
+                Source::Path(Word::from("source2")),
+            ],
+            destination: Word::from("/destination"),
         };
 
         let expected = "ADD source1 source2 /destination";
@@ -426,43 +922,106 @@ mod tests {
 
     #[test]
     fn test_display_instruction_arg() {
-        let instruction = Instruction::Arg(BTreeMap::from([
+        let instruction = Instruction::Arg(vec![
             (String::from("ARG2"), None),
-            (String::from("ARG1"), Some(String::from("value1"))),
-        ]));
+            (String::from("ARG1"), Some(Word::from("value1"))),
+        ]);
 
-        // must be sorted
-        let expected = "ARG ARG1=value1 ARG2";
+        // must keep the source order
+        let expected = "ARG ARG2 ARG1=value1";
         assert_eq!(instruction.to_string(), expected);
     }
 
     #[test]
     fn test_display_instruction_cmd() {
-        let instruction =
-            Instruction::Cmd(vec![String::from("echo"), String::from("Hello, World!")]);
+        let instruction = Instruction::Cmd(Command::Exec(vec![
+            String::from("echo"),
+            String::from("Hello, World!"),
+        ]));
 
         let expected = "CMD [\"echo\", \"Hello, World!\"]";
         assert_eq!(instruction.to_string(), expected);
     }
 
+    #[test]
+    fn test_display_instruction_cmd_with_json_escapes() {
+        let instruction = Instruction::Cmd(Command::Exec(vec![
+            String::from("sh"),
+            String::from("-c"),
+            String::from("echo \"a\tb\""),
+            String::new(),
+        ]));
+
+        let expected = "CMD [\"sh\", \"-c\", \"echo \\\"a\\tb\\\"\", \"\"]";
+        assert_eq!(instruction.to_string(), expected);
+    }
+
+    #[test]
+    fn test_display_instruction_cmd_shell_form() {
+        let instruction = Instruction::Cmd(Command::Shell(String::from("echo \"$HOME\"")));
+
+        let expected = "CMD echo \"$HOME\"";
+        assert_eq!(instruction.to_string(), expected);
+    }
+
     #[test]
     fn test_display_instruction_copy() {
         let instruction = Instruction::Copy {
-            from: Some(String::from("builder")),
+            from: Some(ImageRef::Stage(String::from("builder"))),
             chown: None,
             chmod: None,
-            link: None,
-            sources: vec![String::from("source1"), String::from("source2")],
-            destination: String::from("/destination"),
+            exclude: vec![Word::from("*.log")],
+            link: true,
+            parents: false,
+            sources: vec![
+                Source::Path(Word::from("source1")),
+                Source::Path(Word::from("source2")),
+            ],
+            destination: Word::from("/destination"),
         };
 
-        let expected = "COPY --from=builder source1 source2 /destination";
+        let expected = "COPY --from=builder --exclude=*.log --link source1 source2 /destination";
         assert_eq!(instruction.to_string(), expected);
     }
 
+    #[test]
+    fn test_display_instruction_copy_with_heredoc() {
+        let instruction = Instruction::Copy {
+            from: None,
+            chown: None,
+            chmod: Some(Word::from("755")),
+            exclude: Vec::new(),
+            link: false,
+            parents: false,
+            sources: vec![
+                Source::Heredoc(Heredoc {
+                    delimiter: String::from("EOF"),
+                    strip_tabs: false,
+                    quote: Some(DOUBLE_QUOTE),
+                    body: String::from("#!/bin/sh\necho $HOME\n"),
+                }),
+                Source::Path(Word::from("run.sh")),
+            ],
+            destination: Word::from("/usr/local/bin/"),
+        };
+
+        let expected =
+            "COPY --chmod=755 <<\"EOF\" run.sh /usr/local/bin/\n#!/bin/sh\necho $HOME\nEOF";
+        assert_eq!(instruction.to_string(), expected);
+    }
+
+    #[test]
+    fn test_display_instruction_copy_with_single_quoted_heredoc() {
+        let content = "COPY <<'EOF' /f\necho $HOME\nEOF\n";
+        let dockerfile = Dockerfile::from_str(content).unwrap();
+
+        assert_eq!(dockerfile.instructions[0].to_string(), content.trim_end());
+    }
+
     #[test]
     fn test_display_instruction_entrypoint() {
-        let instruction = Instruction::Entrypoint(vec![String::from("entrypoint.sh")]);
+        let instruction =
+            Instruction::Entrypoint(Command::Exec(vec![String::from("entrypoint.sh")]));
 
         let expected = "ENTRYPOINT [\"entrypoint.sh\"]";
         assert_eq!(instruction.to_string(), expected);
@@ -470,31 +1029,58 @@ mod tests {
 
     #[test]
     fn test_display_instruction_env() {
-        let instruction = Instruction::Env(BTreeMap::from([
-            (String::from("ENV2"), String::from("value2")),
-            (String::from("ENV1"), String::from("value1")),
-        ]));
+        let instruction = Instruction::Env(vec![
+            (Word::from("ENV2"), Word::from("value2")),
+            (Word::from("ENV1"), Word::from("value1")),
+        ]);
 
-        // must be sorted
-        let expected = "ENV ENV1=\"value1\" ENV2=\"value2\"";
+        // must keep the source order
+        let expected = "ENV ENV2=\"value2\" ENV1=\"value1\"";
         assert_eq!(instruction.to_string(), expected);
     }
 
     #[test]
     fn test_display_instruction_expose() {
         let instruction = Instruction::Expose {
-            ports: vec![String::from("80"), String::from("443")],
+            ports: vec!["80".parse().unwrap(), "443/tcp".parse().unwrap()],
         };
 
-        let expected = "EXPOSE 80 443";
+        let expected = "EXPOSE 80 443/tcp";
+        assert_eq!(instruction.to_string(), expected);
+    }
+
+    #[test]
+    fn test_display_instruction_healthcheck_none() {
+        let instruction = Instruction::Healthcheck(Healthcheck::None);
+
+        let expected = "HEALTHCHECK NONE";
+        assert_eq!(instruction.to_string(), expected);
+    }
+
+    #[test]
+    fn test_display_instruction_healthcheck_cmd() {
+        let instruction = Instruction::Healthcheck(Healthcheck::Cmd {
+            interval: Some(Duration::from_secs(90)),
+            timeout: Some(Duration::from_secs(3)),
+            start_period: None,
+            start_interval: Some(Duration::from_millis(1500)),
+            retries: Some(3),
+            command: Command::Exec(vec![
+                String::from("curl"),
+                String::from("-f"),
+                String::from("http://localhost/"),
+            ]),
+        });
+
+        let expected = "HEALTHCHECK --interval=1m30s --timeout=3s --start-interval=1s500ms --retries=3 CMD [\"curl\", \"-f\", \"http://localhost/\"]";
         assert_eq!(instruction.to_string(), expected);
     }
 
     #[test]
     fn test_display_instruction_from() {
         let instruction = Instruction::From {
-            platform: Some(String::from("linux/amd64")),
-            image: String::from("docker.io/library/fedora:latest"),
+            platform: Some(Word::from("linux/amd64")),
+            image: "docker.io/library/fedora:latest".parse().unwrap(),
             alias: Some(String::from("builder")),
         };
 
@@ -504,42 +1090,95 @@ mod tests {
 
     #[test]
     fn test_display_instruction_label() {
-        let instruction = Instruction::Label(BTreeMap::from([
-            (String::from("version"), String::from("1.0")),
-            (String::from("maintainer"), String::from("John Doe")),
-        ]));
+        let instruction = Instruction::Label(vec![
+            (Word::from("version"), Word::from("1.0")),
+            (Word::from("maintainer"), Word::from("John Doe")),
+        ]);
+
+        // must keep the source order
+        let expected = "LABEL version=\"1.0\" maintainer=\"John Doe\"";
+        assert_eq!(instruction.to_string(), expected);
+    }
+
+    #[test]
+    fn test_display_instruction_maintainer() {
+        let instruction = Instruction::Maintainer {
+            name: String::from("John Doe <john.doe@example.com>"),
+        };
 
-        // must be sorted
-        let expected = "LABEL maintainer=\"John Doe\" version=\"1.0\"";
+        let expected = "MAINTAINER John Doe <john.doe@example.com>";
+        assert_eq!(instruction.to_string(), expected);
+    }
+
+    #[test]
+    fn test_display_instruction_onbuild() {
+        let instruction = Instruction::Onbuild(Box::new(Instruction::Run {
+            mounts: Vec::new(),
+            network: None,
+            security: None,
+            command: Command::Shell(String::from("make")),
+            heredocs: Vec::new(),
+        }));
+
+        let expected = "ONBUILD RUN make";
         assert_eq!(instruction.to_string(), expected);
     }
 
     #[test]
     fn test_display_instruction_run() {
         let instruction = Instruction::Run {
-            mount: None,
+            mounts: Vec::new(),
             network: None,
             security: None,
-            command: vec![String::from("cat"), String::from("/etc/os-release")],
-            heredoc: None,
+            command: Command::Shell(String::from("cat /etc/os-release")),
+            heredocs: Vec::new(),
         };
 
         let expected = "RUN cat /etc/os-release";
         assert_eq!(instruction.to_string(), expected);
     }
 
+    #[test]
+    fn test_display_instruction_run_with_mounts() {
+        let instruction = Instruction::Run {
+            mounts: vec![
+                Mount::Secret {
+                    id: Some(String::from("token")),
+                    target: None,
+                    env: None,
+                    required: true,
+                    mode: None,
+                    uid: None,
+                    gid: None,
+                },
+                Mount::Tmpfs {
+                    target: String::from("/tmp"),
+                    size: None,
+                },
+            ],
+            network: Some(String::from("none")),
+            security: None,
+            command: Command::Shell(String::from("make")),
+            heredocs: Vec::new(),
+        };
+
+        let expected = "RUN --mount=type=secret,id=token,required=true --mount=type=tmpfs,target=/tmp --network=none make";
+        assert_eq!(instruction.to_string(), expected);
+    }
+
     #[test]
     fn test_display_instruction_run_with_heredoc() {
         let instruction = Instruction::Run {
-            mount: None,
+            mounts: Vec::new(),
             network: None,
             security: None,
-            command: vec![String::from("<<EOF")],
-            heredoc: Some(vec![
-                String::from("dnf upgrade -y"),
-                String::from("dnf install -y rustup"),
-                String::from("EOF"),
-            ]),
+            command: Command::Shell(String::from("<<EOF")),
+            heredocs: vec![Heredoc {
+                delimiter: String::from("EOF"),
+                strip_tabs: false,
+                quote: None,
+                body: String::from("dnf upgrade -y\ndnf install -y rustup\n"),
+            }],
         };
 
         let expected = "RUN <<EOF\ndnf upgrade -y\ndnf install -y rustup\nEOF";
@@ -549,24 +1188,42 @@ mod tests {
     #[test]
     fn test_display_instruction_run_with_heredoc_and_tabs() {
         let instruction = Instruction::Run {
-            mount: None,
+            mounts: Vec::new(),
             network: None,
             security: None,
-            command: vec![String::from("python"), String::from("<<EOF")],
-            heredoc: Some(vec![
-                String::from("def main():"),
-                String::from("\tx = 42"),
-                String::from("\tprint(x)"),
-                String::from(""),
-                String::from("main()"),
-                String::from("EOF"),
-            ]),
+            command: Command::Shell(String::from("python <<EOF")),
+            heredocs: vec![Heredoc {
+                delimiter: String::from("EOF"),
+                strip_tabs: false,
+                quote: None,
+                body: String::from("def main():\n\tx = 42\n\tprint(x)\n\nmain()\n"),
+            }],
         };
 
         let expected = "RUN python <<EOF\ndef main():\n\tx = 42\n\tprint(x)\n\nmain()\nEOF";
         assert_eq!(instruction.to_string(), expected);
     }
 
+    #[test]
+    fn test_display_instruction_run_with_multiple_heredocs() {
+        let heredoc = |delimiter: &str, body: &str| Heredoc {
+            delimiter: String::from(delimiter),
+            strip_tabs: false,
+            quote: Some(SINGLE_QUOTE),
+            body: String::from(body),
+        };
+        let instruction = Instruction::Run {
+            mounts: Vec::new(),
+            network: None,
+            security: None,
+            command: Command::Shell(String::from("cat <<'A' > /a && cat <<'B' > /b")),
+            heredocs: vec![heredoc("A", "first \\\n"), heredoc("B", "")],
+        };
+
+        let expected = "RUN cat <<'A' > /a && cat <<'B' > /b\nfirst \\\nA\nB";
+        assert_eq!(instruction.to_string(), expected);
+    }
+
     #[test]
     fn test_display_instruction_shell() {
         let instruction = Instruction::Shell(vec![String::from("/bin/sh"), String::from("-c")]);
@@ -578,18 +1235,18 @@ mod tests {
     #[test]
     fn test_display_instruction_user() {
         let instruction = Instruction::User {
-            user: String::from("root"),
-            group: Some(String::from("root")),
+            user: Identity::Name(String::from("root")),
+            group: Some(Identity::Id(0)),
         };
 
-        let expected = "USER root:root";
+        let expected = "USER root:0";
         assert_eq!(instruction.to_string(), expected);
     }
 
     #[test]
     fn test_display_instruction_volume() {
         let instruction = Instruction::Volume {
-            mounts: vec![String::from("/data"), String::from("/var/log")],
+            mounts: vec![Word::from("/data"), Word::from("/var/log")],
         };
 
         let expected = "VOLUME [\"/data\", \"/var/log\"]";
@@ -599,13 +1256,73 @@ mod tests {
     #[test]
     fn test_display_instruction_workdir() {
         let instruction = Instruction::Workdir {
-            path: String::from("/app"),
+            path: WorkdirPath::Absolute(String::from("/app")),
         };
 
         let expected = "WORKDIR /app";
         assert_eq!(instruction.to_string(), expected);
     }
 
+    #[test]
+    fn test_display_instruction_with_escape() {
+        let instruction = Instruction::Label(vec![(
+            Word::from("description"),
+            Word::from("say \"hello\""),
+        )]);
+
+        assert_eq!(
+            instruction.to_string(),
+            "LABEL description=\"say \\\"hello\\\"\""
+        );
+        assert_eq!(
+            instruction.with_escape('`').to_string(),
+            "LABEL description=\"say `\"hello`\"\""
+        );
+    }
+
+    #[test]
+    fn test_display_instruction_parses_back() {
+        let lines = [
+            (r#"ARG A="hello world" B"#, r#"ARG A="hello world" B"#),
+            (r#"COPY "my file.txt" /app/"#, r#"COPY "my file.txt" /app/"#),
+            (r#"WORKDIR "/my dir""#, r#"WORKDIR "/my dir""#),
+            (
+                r#"COPY --exclude="a b" --chown="a b" . /app/"#,
+                r#"COPY --chown="a b" --exclude="a b" . /app/"#,
+            ),
+            (
+                r#"ADD --exclude="a b" --chmod=$MODE "C:\\" $DEST"#,
+                r#"ADD --chmod=$MODE --exclude="a b" "C:\\" $DEST"#,
+            ),
+            (r"ENV P='$HOME'", r#"ENV P="\$HOME""#),
+            (r"LABEL k=C:\\$HOME", r#"LABEL k="C:\\$HOME""#),
+            (
+                r"FROM --platform=\$P alpine",
+                r#"FROM --platform="\$P" alpine"#,
+            ),
+        ];
+
+        for (line, expected) in lines {
+            let instruction = parse_instruction(line, &[], BACKSLASH).unwrap();
+            assert_eq!(instruction.to_string(), expected);
+            assert_eq!(
+                parse_instruction(expected, &[], BACKSLASH).unwrap(),
+                instruction
+            );
+        }
+    }
+
+    #[test]
+    fn test_display_edited_instruction_parses_back() {
+        let instruction = Instruction::Label(vec![(Word::from("k"), Word::from("C:\\$HOME"))]);
+
+        for escape in [BACKSLASH, BACKTICK] {
+            let line = instruction.with_escape(escape).to_string();
+            assert_eq!(parse_instruction(&line, &[], escape).unwrap(), instruction);
+        }
+        assert_eq!(instruction.to_string(), "LABEL k=\"C:\\\\$HOME\"");
+    }
+
     #[test]
     fn test_display_instruction_comment() {
         let instruction = Instruction::Comment(String::from("# This is a comment"));
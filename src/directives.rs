@@ -0,0 +1,194 @@
+// https://docs.docker.com/reference/dockerfile/#parser-directives
+
+use std::fmt;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::error::ErrorDetails;
+use crate::error::ParseError;
+use crate::symbols::chars::BACKSLASH;
+use crate::symbols::chars::BACKTICK;
+
+/// This struct represents the parser directives declared at the top of a Dockerfile.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Directives {
+    /// The location of the Dockerfile syntax used to build the Dockerfile.
+    pub syntax: Option<String>,
+    /// The character used to escape characters and lines.
+    pub escape: Option<char>,
+    /// The configuration of build checks.
+    pub check: Option<String>,
+}
+
+impl Directives {
+    /// Create a new `Directives` instance without any directives set.
+    pub const fn new() -> Self {
+        Self {
+            syntax: None,
+            escape: None,
+            check: None,
+        }
+    }
+
+    /// Return `true` if no directive is set.
+    pub const fn is_empty(&self) -> bool {
+        self.syntax.is_none() && self.escape.is_none() && self.check.is_none()
+    }
+
+    /// Return the escape character, falling back to the default backslash.
+    pub fn escape_char(&self) -> char {
+        self.escape.unwrap_or(BACKSLASH)
+    }
+}
+
+impl fmt::Display for Directives {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(syntax) = &self.syntax {
+            writeln!(f, "# syntax={syntax}")?;
+        }
+        if let Some(escape) = &self.escape {
+            writeln!(f, "# escape={escape}")?;
+        }
+        if let Some(check) = &self.check {
+            writeln!(f, "# check={check}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse the parser directives at the top of the file.
+///
+/// Return the directives together with the number of lines they occupy. An invalid directive ends
+/// the directives and is returned as an error, the lines before it are still parsed.
+pub fn parse_directives(lines: &[String]) -> (Directives, usize, Option<ParseError>) {
+    static RE: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"^#\s*(?P<key>[a-zA-Z][a-zA-Z0-9]*)\s*=\s*(?P<value>.+?)\s*$").unwrap()
+    });
+
+    let mut directives = Directives::new();
+    let mut consumed = 0;
+
+    for (index, line) in lines.iter().enumerate() {
+        // the first comment, empty line or instruction ends the directives
+        let Some(captures) = RE.captures(line) else {
+            break;
+        };
+
+        let key = captures["key"].to_lowercase();
+        let value = captures["value"].to_owned();
+
+        let result = match key.as_str() {
+            "syntax" => set_once(&mut directives.syntax, &captures["key"], value),
+            "escape" => match value.as_str() {
+                "\\" => set_once(&mut directives.escape, &captures["key"], BACKSLASH),
+                "`" => set_once(&mut directives.escape, &captures["key"], BACKTICK),
+                _ => Err(Box::new(ErrorDetails::expected(
+                    "` or \\ as the escape token",
+                    value,
+                ))),
+            },
+            "check" => set_once(&mut directives.check, &captures["key"], value),
+            // unknown directives are treated as comments
+            _ => break,
+        };
+
+        if let Err(mut details) = result {
+            details.locate(index + 1, line);
+            return (directives, consumed, Some(ParseError::SyntaxError(details)));
+        }
+        consumed += 1;
+    }
+
+    (directives, consumed, None)
+}
+
+fn set_once<T>(directive: &mut Option<T>, key: &str, value: T) -> Result<(), Box<ErrorDetails>> {
+    if directive.is_some() {
+        return Err(Box::new(ErrorDetails {
+            found: Some(key.to_owned()),
+            ..ErrorDetails::new(format!(
+                "only one {} parser directive can be used",
+                key.to_lowercase()
+            ))
+        }));
+    }
+    *directive = Some(value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(content: &str) -> Vec<String> {
+        content.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_directives() {
+        let content = "# syntax=docker/dockerfile:1\n#ESCAPE = `\n# check=skip=JSONArgsRecommended\nFROM scratch";
+        let (directives, consumed, error) = parse_directives(&lines(content));
+
+        assert!(error.is_none());
+        assert_eq!(consumed, 3);
+        assert_eq!(
+            directives,
+            Directives {
+                syntax: Some(String::from("docker/dockerfile:1")),
+                escape: Some('`'),
+                check: Some(String::from("skip=JSONArgsRecommended")),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_directives_after_comment_are_comments() {
+        let content = "# This is a comment\n# syntax=docker/dockerfile:1\nFROM scratch";
+        let (directives, consumed, _) = parse_directives(&lines(content));
+
+        assert_eq!(consumed, 0);
+        assert!(directives.is_empty());
+    }
+
+    #[test]
+    fn test_parse_directives_after_unknown_directive_are_comments() {
+        let content = "# unknown=value\n# syntax=docker/dockerfile:1";
+        let (directives, consumed, _) = parse_directives(&lines(content));
+
+        assert_eq!(consumed, 0);
+        assert!(directives.is_empty());
+    }
+
+    #[test]
+    fn test_parse_duplicate_directive_fails() {
+        let content = "# syntax=docker/dockerfile:1\n# Syntax=docker/dockerfile:1";
+        let (directives, consumed, error) = parse_directives(&lines(content));
+
+        assert!(error.is_some());
+        assert_eq!(consumed, 1);
+        assert_eq!(directives.syntax, Some(String::from("docker/dockerfile:1")));
+    }
+
+    #[test]
+    fn test_parse_invalid_escape_fails() {
+        let content = "# escape=/";
+        let (_, _, error) = parse_directives(&lines(content));
+
+        assert!(matches!(error, Some(ParseError::SyntaxError(_))));
+    }
+
+    #[test]
+    fn test_display_directives() {
+        let directives = Directives {
+            syntax: Some(String::from("docker/dockerfile:1")),
+            escape: Some('`'),
+            check: None,
+        };
+
+        let expected = "# syntax=docker/dockerfile:1\n# escape=`\n";
+        assert_eq!(directives.to_string(), expected);
+    }
+}
@@ -1,13 +1,204 @@
+use std::error::Error;
 use std::fmt;
+use std::fmt::Write;
 
-#[derive(Debug)]
+use crate::span::Span;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ParseError {
     FileError(String),
     InternalError(String),
-    MissingArgument(String),
-    SyntaxError(String),
-    UnknownInstruction(String),
-    WrongNumberOfArguments(String),
+    MissingArgument(Box<ErrorDetails>),
+    SyntaxError(Box<ErrorDetails>),
+    UnknownInstruction(Box<ErrorDetails>),
+    WrongNumberOfArguments(Box<ErrorDetails>),
+}
+
+/// This struct represents the details of an error found in the content of a Dockerfile.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorDetails {
+    /// A description of the problem.
+    pub message: String,
+    /// The keyword of the offending instruction.
+    pub instruction: Option<String>,
+    /// What the parser expected to find.
+    pub expected: Option<String>,
+    /// What the parser found instead.
+    pub found: Option<String>,
+    /// The line of the problem, counting from 1.
+    pub line: Option<usize>,
+    /// The column of the problem, counting from 1.
+    pub column: Option<usize>,
+    /// The source line containing the problem.
+    pub snippet: Option<String>,
+}
+
+impl ErrorDetails {
+    /// Create error details with the given description.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Create error details describing what was expected and what was found instead.
+    pub fn expected(expected: impl Into<String>, found: impl Into<String>) -> Self {
+        let expected = expected.into();
+        let found = found.into();
+        Self {
+            message: format!("expected {expected}, found {found}"),
+            expected: Some(expected),
+            found: Some(found),
+            ..Self::default()
+        }
+    }
+
+    /// Point the error at the given source line.
+    ///
+    /// The column is derived from the position of the found text, if there is any.
+    pub(crate) fn locate(&mut self, line: usize, text: &str) {
+        let index = self
+            .found
+            .as_deref()
+            .filter(|found| !found.is_empty())
+            .and_then(|found| find_word(text, found))
+            .unwrap_or_else(|| text.len() - text.trim_start().len());
+
+        self.line = Some(line);
+        self.column = Some(text[..index].chars().count() + 1);
+        self.snippet = Some(text.to_owned());
+    }
+}
+
+impl fmt::Display for ErrorDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, "line {line}, column {column}: ")?;
+        }
+        match &self.instruction {
+            Some(instruction) if !self.message.starts_with(instruction.as_str()) => {
+                write!(f, "{instruction}: {}", self.message)
+            }
+            _ => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl ParseError {
+    /// Return the details of errors found in the content of a Dockerfile.
+    pub fn details(&self) -> Option<&ErrorDetails> {
+        match self {
+            Self::FileError(_) | Self::InternalError(_) => None,
+            Self::MissingArgument(details)
+            | Self::SyntaxError(details)
+            | Self::UnknownInstruction(details)
+            | Self::WrongNumberOfArguments(details) => Some(details.as_ref()),
+        }
+    }
+
+    fn details_mut(&mut self) -> Option<&mut ErrorDetails> {
+        match self {
+            Self::FileError(_) | Self::InternalError(_) => None,
+            Self::MissingArgument(details)
+            | Self::SyntaxError(details)
+            | Self::UnknownInstruction(details)
+            | Self::WrongNumberOfArguments(details) => Some(details.as_mut()),
+        }
+    }
+
+    /// Attach the keyword of the offending instruction, unless there is one already.
+    pub(crate) fn with_instruction(mut self, instruction: &str) -> Self {
+        if let Some(details) = self.details_mut() {
+            details
+                .instruction
+                .get_or_insert_with(|| instruction.to_uppercase());
+        }
+        self
+    }
+
+    /// Attach the location of the offending instruction in the given text.
+    pub(crate) fn with_span(mut self, text: &str, span: Span) -> Self {
+        if let Some(details) = self.details_mut() {
+            let content = span.slice(text).unwrap_or_default();
+            // find the physical line containing the problem
+            let index = details
+                .found
+                .as_deref()
+                .filter(|found| !found.is_empty())
+                .and_then(|found| find_word(content, found))
+                .unwrap_or_default();
+            let start = content[..index].rfind('\n').map_or(0, |i| i + 1);
+            let end = content[index..]
+                .find('\n')
+                .map_or(content.len(), |i| index + i);
+
+            let line = span.start_line + content[..start].matches('\n').count();
+            let snippet = content[start..end].trim_end_matches('\r');
+            details.locate(line, snippet);
+        }
+        self
+    }
+
+    /// Render the error together with the offending source line and a caret pointing at the
+    /// problem.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    ///
+    /// use dockerfile_parser_rs::Dockerfile;
+    ///
+    /// let error = Dockerfile::from_str("FROM scratch\nHEALTHCHECK CHECK true\n").unwrap_err();
+    /// assert_eq!(
+    ///     error.render(),
+    ///     "Syntax error: line 2, column 13: HEALTHCHECK: expected NONE or CMD, found CHECK\n  |\n2 | HEALTHCHECK CHECK true\n  |             ^^^^^\n"
+    /// );
+    /// ```
+    pub fn render(&self) -> String {
+        let mut result = format!("{self}\n");
+
+        let Some(details) = self.details() else {
+            return result;
+        };
+        let (Some(line), Some(column), Some(snippet)) =
+            (details.line, details.column, &details.snippet)
+        else {
+            return result;
+        };
+
+        let number = line.to_string();
+        let padding = " ".repeat(number.len());
+        let width = details
+            .found
+            .as_deref()
+            .filter(|found| snippet.contains(*found))
+            .map_or(1, |found| found.chars().count().max(1));
+
+        let _ = writeln!(result, "{padding} |");
+        let _ = writeln!(result, "{number} | {snippet}");
+        let _ = writeln!(
+            result,
+            "{padding} | {}{}",
+            " ".repeat(column - 1),
+            "^".repeat(width)
+        );
+        result
+    }
+}
+
+/// Find the found text in the source, preferring occurrences that are not part of a longer word.
+fn find_word(text: &str, found: &str) -> Option<usize> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(found)
+        .map(|(index, _)| index)
+        .find(|&index| {
+            let before = text[..index].chars().next_back();
+            let after = text[index + found.len()..].chars().next();
+            !before.is_some_and(is_word) && !after.is_some_and(is_word)
+        })
+        .or_else(|| text.find(found))
 }
 
 impl fmt::Display for ParseError {
@@ -15,10 +206,69 @@ impl fmt::Display for ParseError {
         match self {
             Self::FileError(msg) => write!(f, "File error: {msg}"),
             Self::InternalError(msg) => write!(f, "Internal error: {msg}"),
-            Self::MissingArgument(msg) => write!(f, "Missing argument: {msg}"),
-            Self::SyntaxError(msg) => write!(f, "Syntax error: {msg}"),
-            Self::UnknownInstruction(msg) => write!(f, "Unknown instruction: {msg}"),
-            Self::WrongNumberOfArguments(msg) => write!(f, "Wrong number of arguments: {msg}"),
+            Self::MissingArgument(details) => write!(f, "Missing argument: {details}"),
+            Self::SyntaxError(details) => write!(f, "Syntax error: {details}"),
+            Self::UnknownInstruction(details) => write!(f, "Unknown instruction: {details}"),
+            Self::WrongNumberOfArguments(details) => {
+                write!(f, "Wrong number of arguments: {details}")
+            }
         }
     }
 }
+
+impl Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_details_expected() {
+        let details = ErrorDetails::expected("NONE or CMD", "CHECK");
+
+        assert_eq!(details.message, "expected NONE or CMD, found CHECK");
+        assert_eq!(details.expected, Some(String::from("NONE or CMD")));
+        assert_eq!(details.found, Some(String::from("CHECK")));
+    }
+
+    #[test]
+    fn test_error_with_span() {
+        let text = "FROM scratch\nRUN --network=host \\\n    --security=wrong true\n";
+        let span = Span {
+            start_line: 2,
+            end_line: 3,
+            start: 13,
+            end: 59,
+        };
+        let details = ErrorDetails::expected("sandbox or insecure", "wrong");
+        let error = ParseError::SyntaxError(Box::new(details))
+            .with_instruction("run")
+            .with_span(text, span);
+
+        let details = error.details().unwrap();
+        assert_eq!(details.instruction, Some(String::from("RUN")));
+        assert_eq!(details.line, Some(3));
+        assert_eq!(details.column, Some(16));
+        assert_eq!(
+            details.snippet,
+            Some(String::from("    --security=wrong true"))
+        );
+    }
+
+    #[test]
+    fn test_render_error() {
+        let mut details = ErrorDetails::expected("a Dockerfile instruction", "MAKE");
+        details.locate(7, "MAKE love");
+        let error = ParseError::UnknownInstruction(Box::new(details));
+
+        let expected = "Unknown instruction: line 7, column 1: expected a Dockerfile instruction, found MAKE\n  |\n7 | MAKE love\n  | ^^^^\n";
+        assert_eq!(error.render(), expected);
+    }
+
+    #[test]
+    fn test_render_error_without_location() {
+        let error = ParseError::FileError(String::from("No such file or directory"));
+
+        assert_eq!(error.render(), "File error: No such file or directory\n");
+    }
+}
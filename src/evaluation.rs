@@ -0,0 +1,490 @@
+// https://docs.docker.com/reference/dockerfile/#scope
+
+use std::collections::HashMap;
+
+use crate::ParseResult;
+use crate::ast::Instruction;
+use crate::error::ErrorDetails;
+use crate::error::ParseError;
+use crate::image::ImageRef;
+use crate::platform::Platform;
+use crate::word::Word;
+
+/// This struct represents the options of a build, like the `--build-arg`, `--target` and
+/// `--platform` options of `docker build`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildArgs {
+    /// The values of the build arguments, overriding the defaults of the declared `ARG`s.
+    pub args: HashMap<String, String>,
+    /// The name of the stage to build, the last stage if not set.
+    pub target: Option<String>,
+    /// The platform to build for, used for the automatic platform arguments like `TARGETPLATFORM`.
+    ///
+    /// The stages without a `--platform` option are left without one.
+    pub platform: Option<Platform>,
+    /// The platform the build runs on, the host platform if not set.
+    pub build_platform: Option<Platform>,
+}
+
+impl BuildArgs {
+    /// Create build options without any build arguments, target or platform.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the automatic platform arguments, like `TARGETARCH` or `BUILDPLATFORM`.
+    ///
+    /// The target platform falls back to the build platform, which falls back to the host
+    /// platform.
+    pub fn platform_args(&self) -> HashMap<String, String> {
+        let build = self.build_platform.clone().unwrap_or_else(Platform::host);
+        let target = self.platform.as_ref().unwrap_or(&build);
+
+        target
+            .args("TARGET")
+            .into_iter()
+            .chain(build.args("BUILD"))
+            .collect()
+    }
+}
+
+/// The state of the evaluation while walking through the instructions.
+struct Evaluation<'a> {
+    build: &'a BuildArgs,
+    escape: char,
+    /// The values of the global arguments declared before the first stage.
+    globals: HashMap<String, String>,
+    /// The names of the stages so far.
+    stages: Vec<Option<String>>,
+    /// The environment of each stage so far.
+    envs: Vec<HashMap<String, String>>,
+    /// The values of the arguments declared in the current stage.
+    args: HashMap<String, String>,
+}
+
+impl Evaluation<'_> {
+    /// Return the variables visible to the next instruction.
+    fn variables(&self) -> HashMap<String, String> {
+        let Some(env) = self.envs.last() else {
+            return self.globals.clone();
+        };
+        // the environment takes precedence over the arguments
+        let mut variables = self.args.clone();
+        variables.extend(env.clone());
+        variables
+    }
+
+    /// Declare an argument with its expanded default and return its value, if there is any.
+    fn declare(&mut self, name: &str, default: Option<&Word>) -> Option<Word> {
+        let value = match (self.build.args.get(name), default) {
+            (Some(value), _) => Some(value.clone()),
+            (None, Some(default)) => Some(default.to_string()),
+            // redeclaring a global argument inside a stage makes its value visible
+            (None, None) => self.globals.get(name).cloned(),
+        };
+
+        if let Some(value) = &value {
+            let scope = if self.envs.is_empty() {
+                &mut self.globals
+            } else {
+                &mut self.args
+            };
+            scope.insert(name.to_owned(), value.clone());
+        }
+        // the value is not expanded again
+        value.map(Word::literal)
+    }
+
+    /// Start a new stage, inheriting the environment of the stage it is based on.
+    fn start_stage(&mut self, image: &ImageRef, alias: Option<&String>) {
+        let env = match image {
+            ImageRef::Stage(name) => self
+                .stages
+                .iter()
+                .rposition(|stage| stage.as_ref().is_some_and(|s| s.eq_ignore_ascii_case(name)))
+                .or_else(|| name.parse().ok().filter(|index| *index < self.stages.len()))
+                .map(|index| self.envs[index].clone())
+                .unwrap_or_default(),
+            _ => HashMap::new(),
+        };
+
+        self.stages.push(alias.cloned());
+        self.envs.push(env);
+        self.args.clear();
+    }
+
+    fn evaluate(&mut self, instruction: &Instruction) -> ParseResult<Instruction> {
+        let substitute =
+            |variables: &HashMap<String, String>| instruction.substitute(variables, self.escape);
+
+        match instruction {
+            Instruction::Arg(_) => {
+                // the defaults only see the variables declared before the instruction
+                let mut instruction = substitute(&self.variables())?;
+                if let Instruction::Arg(args) = &mut instruction {
+                    for (name, default) in args {
+                        *default = self.declare(name, default.as_ref());
+                    }
+                }
+                Ok(instruction)
+            }
+            // the triggers are expanded in the downstream build
+            Instruction::Onbuild(_) => Ok(instruction.clone()),
+            Instruction::From { alias, .. } => {
+                // only the global arguments are visible to FROM
+                let mut instruction = substitute(&self.globals)?;
+                if let Instruction::From {
+                    image,
+                    platform: resolved,
+                    ..
+                } = &mut instruction
+                {
+                    image.resolve_stage(&self.stages, false);
+                    if let Some(resolved) = resolved {
+                        *resolved = Word::literal(resolved.parse::<Platform>()?.to_string());
+                    }
+                    self.start_stage(image, alias.as_ref());
+                }
+                Ok(instruction)
+            }
+            _ => {
+                let mut instruction = substitute(&self.variables())?;
+                match &mut instruction {
+                    Instruction::Copy {
+                        from: Some(from), ..
+                    } => from.resolve_stage(&self.stages, true),
+                    Instruction::Env(pairs) => {
+                        if let Some(env) = self.envs.last_mut() {
+                            env.extend(
+                                pairs
+                                    .iter()
+                                    .map(|(key, value)| (key.to_string(), value.to_string())),
+                            );
+                        }
+                    }
+                    _ => {}
+                }
+                Ok(instruction)
+            }
+        }
+    }
+}
+
+/// Resolve the instructions for the given build options.
+///
+/// The errors are passed to `locate` together with the index of the offending instruction.
+pub fn evaluate_instructions(
+    instructions: &[Instruction],
+    build: &BuildArgs,
+    escape: char,
+    locate: impl Fn(usize, ParseError) -> ParseError,
+) -> ParseResult<Vec<Instruction>> {
+    if let Some(target) = &build.target {
+        let found = instructions.iter().any(|instruction| match instruction {
+            Instruction::From {
+                alias: Some(alias), ..
+            } => alias.eq_ignore_ascii_case(target),
+            _ => false,
+        });
+        if !found {
+            return Err(ParseError::MissingArgument(Box::new(ErrorDetails {
+                found: Some(target.clone()),
+                ..ErrorDetails::new(format!("target stage {target} could not be found"))
+            })));
+        }
+    }
+
+    let mut evaluation = Evaluation {
+        build,
+        escape,
+        // the automatic platform arguments are declared in the global scope
+        globals: build.platform_args(),
+        stages: Vec::new(),
+        envs: Vec::new(),
+        args: HashMap::new(),
+    };
+
+    let mut result = Vec::new();
+    for (index, instruction) in instructions.iter().enumerate() {
+        // the stages following the target are not built
+        let is_target = |stage: &Option<String>| {
+            stage
+                .as_ref()
+                .zip(build.target.as_ref())
+                .is_some_and(|(stage, target)| stage.eq_ignore_ascii_case(target))
+        };
+        if matches!(instruction, Instruction::From { .. })
+            && evaluation.stages.last().is_some_and(is_target)
+        {
+            break;
+        }
+        let instruction = evaluation
+            .evaluate(instruction)
+            .map_err(|error| locate(index, error))?;
+        result.push(instruction);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::ast::Source;
+    use crate::file::Dockerfile;
+
+    fn evaluate(content: &str, build: &BuildArgs) -> Vec<String> {
+        let dockerfile = Dockerfile::from_str(content).unwrap();
+        let dockerfile = dockerfile.evaluate(build).unwrap();
+        dockerfile
+            .instructions
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    fn build_args(args: &[(&str, &str)]) -> BuildArgs {
+        BuildArgs {
+            args: args
+                .iter()
+                .map(|(name, value)| (String::from(*name), String::from(*value)))
+                .collect(),
+            ..BuildArgs::new()
+        }
+    }
+
+    #[test]
+    fn test_evaluate_global_args_in_from() {
+        let content = "ARG BASE=alpine\nARG TAG=3.20\nFROM ${BASE}:${TAG}\n";
+
+        let result = evaluate(content, &BuildArgs::new());
+        assert_eq!(result[2], "FROM alpine:3.20");
+
+        let result = evaluate(content, &build_args(&[("TAG", "edge")]));
+        assert_eq!(result[1], "ARG TAG=edge");
+        assert_eq!(result[2], "FROM alpine:edge");
+    }
+
+    #[test]
+    fn test_evaluate_global_args_are_not_visible_in_stages() {
+        let mut content = String::new();
+        content.push_str("ARG VERSION=1.0\n");
+        content.push_str("FROM scratch\n");
+        content.push_str("WORKDIR /before/${VERSION-unset}\n");
+        content.push_str("ARG VERSION\n");
+        content.push_str("WORKDIR /after/${VERSION-unset}\n");
+
+        let result = evaluate(&content, &BuildArgs::new());
+        assert_eq!(result[2], "WORKDIR /before/unset");
+        assert_eq!(result[3], "ARG VERSION=1.0");
+        assert_eq!(result[4], "WORKDIR /after/1.0");
+    }
+
+    #[test]
+    fn test_evaluate_env_takes_precedence_over_arg() {
+        let mut content = String::new();
+        content.push_str("FROM scratch\n");
+        content.push_str("ARG MODE=debug\n");
+        content.push_str("ENV MODE=release\n");
+        content.push_str("WORKDIR /build/$MODE\n");
+
+        let result = evaluate(&content, &build_args(&[("MODE", "test")]));
+        assert_eq!(result[1], "ARG MODE=test");
+        assert_eq!(result[3], "WORKDIR /build/release");
+    }
+
+    #[test]
+    fn test_evaluate_env_keys() {
+        let mut content = String::new();
+        content.push_str("FROM scratch\n");
+        content.push_str("ARG K=name\n");
+        content.push_str("ENV $K=v\n");
+        content.push_str("WORKDIR /$name\n");
+
+        let result = evaluate(&content, &BuildArgs::new());
+        assert_eq!(result[2], "ENV name=\"v\"");
+        assert_eq!(result[3], "WORKDIR /v");
+    }
+
+    #[test]
+    fn test_evaluate_quoted_and_escaped_dollars() {
+        let mut content = String::new();
+        content.push_str("FROM scratch\n");
+        content.push_str("ARG B=x\n");
+        content.push_str("WORKDIR a\\\\$B\n");
+        content.push_str("ENV A='$HOME' C=\"$B \\$B\"\n");
+        content.push_str("LABEL $B=\\$B\n");
+
+        let dockerfile = Dockerfile::from_str(&content).unwrap();
+        let dockerfile = dockerfile.evaluate(&BuildArgs::new()).unwrap();
+        assert_eq!(dockerfile.instructions[2].to_string(), "WORKDIR \"a\\x\"");
+        assert_eq!(
+            dockerfile.instructions[3],
+            Instruction::Env(vec![
+                (Word::from("A"), Word::literal("$HOME")),
+                (Word::from("C"), Word::literal("x $B")),
+            ])
+        );
+        assert_eq!(
+            dockerfile.instructions[4],
+            Instruction::Label(vec![(Word::from("x"), Word::literal("$B"))])
+        );
+    }
+
+    #[test]
+    fn test_evaluate_modified_instructions_are_plain() {
+        let mut dockerfile = Dockerfile::from_str("FROM scratch\nARG B=x\n").unwrap();
+        dockerfile.instructions.push(Instruction::Env(vec![(
+            Word::from("A"),
+            Word::from("'$B'"),
+        )]));
+
+        let dockerfile = dockerfile.evaluate(&BuildArgs::new()).unwrap();
+        assert_eq!(
+            dockerfile.instructions[2],
+            Instruction::Env(vec![(Word::from("A"), Word::from("'x'"))])
+        );
+    }
+
+    #[test]
+    fn test_evaluate_exec_form_values() {
+        let content = "FROM scratch\nARG D=/app\nCOPY --chmod=755 [\"a b\", \"$D/\"]\n";
+
+        let dockerfile = Dockerfile::from_str(content).unwrap();
+        let dockerfile = dockerfile.evaluate(&BuildArgs::new()).unwrap();
+        let Instruction::Copy {
+            sources,
+            destination,
+            ..
+        } = &dockerfile.instructions[2]
+        else {
+            panic!("expected COPY");
+        };
+        assert_eq!(sources, &vec![Source::Path(Word::from("a b"))]);
+        assert_eq!(destination, "/app/");
+    }
+
+    #[test]
+    fn test_evaluate_exec_form_values_without_shell_quotes() {
+        let content = "FROM scratch\nCOPY [\"it's\", \"C:\\\\path\", \"/app\"]\n";
+
+        let dockerfile = Dockerfile::from_str(content).unwrap();
+        let dockerfile = dockerfile.evaluate(&BuildArgs::new()).unwrap();
+        let Instruction::Copy { sources, .. } = &dockerfile.instructions[1] else {
+            panic!("expected COPY");
+        };
+        assert_eq!(
+            sources,
+            &vec![
+                Source::Path(Word::from("it's")),
+                Source::Path(Word::from("C:\\path")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_evaluate_keeps_onbuild_triggers() {
+        let mut content = String::new();
+        content.push_str("FROM scratch\n");
+        content.push_str("ARG V=1\n");
+        content.push_str("ONBUILD WORKDIR /$V\n");
+
+        let result = evaluate(&content, &BuildArgs::new());
+        assert_eq!(result[2], "ONBUILD WORKDIR /$V");
+    }
+
+    #[test]
+    fn test_evaluate_stages_inherit_environment() {
+        let mut content = String::new();
+        content.push_str("FROM alpine AS base\n");
+        content.push_str("ENV PREFIX=/opt ARCH=x86_64\n");
+        content.push_str("ARG USER=app\n");
+        content.push_str("FROM base\n");
+        content.push_str("WORKDIR $PREFIX/$ARCH/${USER-root}\n");
+
+        let result = evaluate(&content, &BuildArgs::new());
+        assert_eq!(result[4], "WORKDIR /opt/x86_64/root");
+    }
+
+    #[test]
+    fn test_evaluate_target_and_platform() {
+        let mut content = String::new();
+        content.push_str("FROM alpine AS build\n");
+        content.push_str("FROM --platform=linux/amd64 alpine AS test\n");
+        content.push_str("FROM scratch\n");
+
+        let build = BuildArgs {
+            target: Some(String::from("test")),
+            platform: Some("linux/arm64".parse().unwrap()),
+            ..BuildArgs::new()
+        };
+        let result = evaluate(&content, &build);
+        assert_eq!(
+            result,
+            vec![
+                "FROM alpine AS build",
+                "FROM --platform=linux/amd64 alpine AS test",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_evaluate_platform_args() {
+        let mut content = String::new();
+        content.push_str("FROM --platform=$BUILDPLATFORM golang AS build\n");
+        content.push_str("WORKDIR /src/$TARGETARCH\n");
+        content.push_str("ARG TARGETOS TARGETARCH TARGETVARIANT\n");
+        content.push_str("WORKDIR /out/$TARGETOS/$TARGETARCH/$TARGETVARIANT\n");
+        content.push_str("FROM --platform=$TARGETPLATFORM alpine\n");
+
+        let build = BuildArgs {
+            platform: Some("linux/armhf".parse().unwrap()),
+            build_platform: Some("linux/x86_64".parse().unwrap()),
+            ..BuildArgs::new()
+        };
+        let result = evaluate(&content, &build);
+        assert_eq!(result[0], "FROM --platform=linux/amd64 golang AS build");
+        assert_eq!(result[1], "WORKDIR /src/");
+        assert_eq!(
+            result[2],
+            "ARG TARGETOS=linux TARGETARCH=arm TARGETVARIANT=v7"
+        );
+        assert_eq!(result[3], "WORKDIR /out/linux/arm/v7");
+        assert_eq!(result[4], "FROM --platform=linux/arm/v7 alpine");
+    }
+
+    #[test]
+    fn test_evaluate_invalid_platform_fails() {
+        let dockerfile = Dockerfile::from_str("FROM --platform=linux alpine\n").unwrap();
+
+        assert!(matches!(
+            dockerfile.evaluate(&BuildArgs::new()),
+            Err(ParseError::SyntaxError(_))
+        ));
+    }
+
+    #[test]
+    fn test_evaluate_unknown_target_fails() {
+        let dockerfile = Dockerfile::from_str("FROM alpine AS build\n").unwrap();
+        let build = BuildArgs {
+            target: Some(String::from("release")),
+            ..BuildArgs::new()
+        };
+
+        assert!(matches!(
+            dockerfile.evaluate(&build),
+            Err(ParseError::MissingArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_evaluate_error_is_located() {
+        let dockerfile = Dockerfile::from_str("FROM scratch\nWORKDIR ${DIR:?required}\n").unwrap();
+        let error = dockerfile.evaluate(&BuildArgs::new()).unwrap_err();
+        let details = error.details().unwrap();
+
+        assert_eq!(details.instruction, Some(String::from("WORKDIR")));
+        assert_eq!(details.line, Some(2));
+    }
+}
@@ -1,6 +1,5 @@
+use std::fs;
 use std::fs::File;
-use std::io::BufRead;
-use std::io::BufReader;
 use std::io::Write;
 use std::path::PathBuf;
 use std::str::FromStr;
@@ -10,47 +9,161 @@ use serde::Serialize;
 
 use crate::ParseResult;
 use crate::ast::Instruction;
+use crate::directives::Directives;
 use crate::error::ParseError;
-use crate::parser::instructions::add;
-use crate::parser::instructions::arg;
-use crate::parser::instructions::cmd;
-use crate::parser::instructions::copy;
-use crate::parser::instructions::entrypoint;
-use crate::parser::instructions::env;
-use crate::parser::instructions::expose;
-use crate::parser::instructions::from;
-use crate::parser::instructions::label;
-use crate::parser::instructions::run;
-use crate::parser::instructions::shell;
-use crate::parser::instructions::stopsignal;
-use crate::parser::instructions::user;
-use crate::parser::instructions::volume;
-use crate::parser::instructions::workdir;
-use crate::symbols::chars::HASHTAG;
-use crate::utils::process_dockerfile_content;
-use crate::utils::split_instruction_and_arguments;
+use crate::evaluation::BuildArgs;
+use crate::evaluation::evaluate_instructions;
+use crate::parser::parse_instruction;
+use crate::span::Span;
+use crate::stage::Stage;
+use crate::stage::StageMut;
+use crate::stage::count_layers;
+use crate::stage::count_steps;
+use crate::stage::find_stage;
+use crate::stage::stage_ranges;
+use crate::symbols::chars::SPACE;
+use crate::syntax::NodeKind;
+use crate::syntax::SyntaxNode;
+use crate::syntax::SyntaxTree;
+use crate::utils::align;
+use crate::utils::restore_keywords;
+use crate::utils::split_keywords;
+use crate::word::Word;
+
+// https://github.com/opencontainers/image-spec/blob/main/annotations.md
+const MAINTAINER_LABEL: &str = "org.opencontainers.image.authors";
 
 /// This struct represents a Dockerfile instance.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, Serialize, Deserialize)]
 pub struct Dockerfile {
+    #[serde(default)]
+    pub directives: Directives,
     pub instructions: Vec<Instruction>,
+    /// The syntax tree the instructions were derived from.
+    #[serde(skip)]
+    tree: SyntaxTree,
+    /// How each parsed instruction was written in the original text, in the parsed order.
+    #[serde(skip)]
+    origins: Vec<Origin>,
+}
+
+/// This struct represents how an instruction was written in the original text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Origin {
+    /// The keywords of the instruction, spelled as in the original text.
+    keywords: Vec<String>,
+    /// The index of the node in the syntax tree.
+    node: usize,
+    /// The instruction as it was parsed, to find it among the current instructions.
+    instruction: Instruction,
+}
+
+impl PartialEq for Dockerfile {
+    fn eq(&self, other: &Self) -> bool {
+        // the original text does not change the meaning
+        self.directives == other.directives && self.instructions == other.instructions
+    }
 }
 
 impl FromStr for Dockerfile {
     type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let lines = process_dockerfile_content(s.lines().map(String::from));
-
-        let instructions = parse(lines)?;
-        Ok(Self::new(instructions))
+        let (dockerfile, mut errors) = Self::parse_text(s, false);
+        errors.pop().map_or(Ok(dockerfile), Err)
     }
 }
 
 impl Dockerfile {
+    /// Parse the content of a Dockerfile without stopping at the first error.
+    ///
+    /// Lines that cannot be parsed are kept as `Instruction::Invalid` holding their original text,
+    /// so the rest of the file can still be inspected. Return the partial `Dockerfile` together
+    /// with all errors found in the content.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use dockerfile_parser_rs::Dockerfile;
+    /// use dockerfile_parser_rs::Instruction;
+    ///
+    /// let (dockerfile, errors) = Dockerfile::parse_tolerant("FROM scratch\nMAKE love\nUSER 1001\n");
+    ///
+    /// assert_eq!(errors.len(), 1);
+    /// assert_eq!(dockerfile.instructions[1], Instruction::Invalid(String::from("MAKE love")));
+    /// ```
+    pub fn parse_tolerant(content: &str) -> (Self, Vec<ParseError>) {
+        Self::parse_text(content, true)
+    }
+
+    /// Parse the text, stopping at the first error unless the parsing is tolerant.
+    fn parse_text(s: &str, tolerant: bool) -> (Self, Vec<ParseError>) {
+        let mut errors = Vec::new();
+
+        let (tree, error) = SyntaxTree::build(s);
+        if let Some(error) = error {
+            errors.push(error);
+            if !tolerant {
+                return (Self::empty(), errors);
+            }
+        }
+
+        // derive the typed instructions from the syntax tree
+        let directives = tree.directives().clone();
+        let escape = directives.escape_char();
+        let mut instructions = Vec::new();
+        let mut origins = Vec::new();
+        // the names of the stages so far, to tell stage references from images
+        let mut stages = Vec::new();
+        for (index, node) in tree.nodes().iter().enumerate() {
+            if node.kind == NodeKind::Directive {
+                continue;
+            }
+            let mut instruction = match parse(node, escape) {
+                Ok(instruction) => instruction,
+                Err(error) => {
+                    errors.push(error.with_span(s, node.span));
+                    if !tolerant {
+                        return (Self::empty(), errors);
+                    }
+                    Instruction::Invalid(node.text.clone())
+                }
+            };
+            match &mut instruction {
+                Instruction::From { image, alias, .. } => {
+                    image.resolve_stage(&stages, false);
+                    stages.push(alias.clone());
+                }
+                Instruction::Copy {
+                    from: Some(from), ..
+                } => from.resolve_stage(&stages, true),
+                _ => {}
+            }
+            origins.push(Origin {
+                keywords: split_keywords(node.content()),
+                node: index,
+                instruction: instruction.clone(),
+            });
+            instructions.push(instruction);
+        }
+
+        let dockerfile = Self {
+            directives,
+            instructions,
+            tree,
+            origins,
+        };
+        (dockerfile, errors)
+    }
+
     /// Create a new `Dockerfile` instance for the given instructions.
     pub const fn new(instructions: Vec<Instruction>) -> Self {
-        Self { instructions }
+        Self {
+            directives: Directives::new(),
+            instructions,
+            tree: SyntaxTree::new(),
+            origins: Vec::new(),
+        }
     }
 
     /// Create an empty `Dockerfile` instance.
@@ -85,28 +198,226 @@ impl Dockerfile {
     ///
     /// Return an error if the file cannot be opened or if there is a syntax error in the Dockerfile.
     pub fn from(path: PathBuf) -> ParseResult<Self> {
-        let file = File::open(path).map_err(|e| ParseError::FileError(e.to_string()))?;
-        let reader = BufReader::new(file);
-        let lines = process_dockerfile_content(reader.lines().map_while(Result::ok));
+        let content = fs::read_to_string(path).map_err(|e| ParseError::FileError(e.to_string()))?;
+        Self::from_str(&content)
+    }
+
+    /// Return the location of the instruction at the given index in the parsed text.
+    ///
+    /// The spans are kept in a separate table of the parsed instructions, which is matched with
+    /// `Dockerfile::instructions` on every call. The span follows the instruction when other
+    /// instructions are inserted or removed before it. Return `None` for instructions that were not
+    /// parsed from text, including instructions that were inserted or moved since. Spans always
+    /// refer to the original text, so an instruction modified in place keeps the span of the line
+    /// it was parsed from.
+    pub fn span(&self, index: usize) -> Option<Span> {
+        self.origins()
+            .get(index)
+            .copied()
+            .flatten()
+            .map(|o| self.node(o).span)
+    }
+
+    /// Find the origin of each instruction, following the instructions that were inserted,
+    /// removed or modified since parsing.
+    fn origins(&self) -> Vec<Option<&Origin>> {
+        align(&self.instructions, &self.origins, |instruction, origin| {
+            *instruction == origin.instruction
+        })
+        .into_iter()
+        .map(|index| index.map(|index| &self.origins[index]))
+        .collect()
+    }
 
-        let instructions = parse(lines)?;
-        Ok(Self::new(instructions))
+    fn node(&self, origin: &Origin) -> &SyntaxNode {
+        &self.tree.nodes()[origin.node]
+    }
+
+    /// Return the lossless syntax tree the Dockerfile was parsed from.
+    ///
+    /// The tree is empty for a `Dockerfile` that was not parsed from text. It always describes the
+    /// original text, so it is not updated when the instructions are modified.
+    pub const fn syntax_tree(&self) -> &SyntaxTree {
+        &self.tree
+    }
+
+    /// Return an iterator over the instructions together with their location in the parsed text.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    ///
+    /// use dockerfile_parser_rs::Dockerfile;
+    ///
+    /// let dockerfile = Dockerfile::from_str("FROM scratch\nCOPY app \\\n  /app\n").unwrap();
+    /// for (span, instruction) in dockerfile.spanned() {
+    ///     let span = span.unwrap();
+    ///     println!("{}-{}: {instruction}", span.start_line, span.end_line);
+    /// }
+    /// ```
+    pub fn spanned(&self) -> impl Iterator<Item = (Option<Span>, &Instruction)> {
+        self.origins()
+            .into_iter()
+            .zip(&self.instructions)
+            .map(|(origin, instruction)| (origin.map(|o| self.node(o).span), instruction))
     }
 
-    /// Dump the instructions to a file.
+    /// Dump the parser directives and instructions to a file.
     ///
     /// If the file does not exist, it will be created.
     /// If the file exists, it will be overwritten.
     ///
+    /// Instructions that were not modified since parsing are written exactly as in the original
+    /// text, so dumping a parsed `Dockerfile` without edits reproduces the file byte for byte.
+    /// The escape character declared by the `escape` directive is used for escaped quotes.
+    ///
     /// ## Errors
     ///
     /// Return an error if the file cannot be created or written to.
     pub fn dump(&self, path: PathBuf) -> ParseResult<()> {
+        self.write(path, false)
+    }
+
+    /// Dump the parser directives and instructions to a file, spelling the keywords exactly as
+    /// they were written in the parsed Dockerfile.
+    ///
+    /// Keywords of instructions that were not parsed from a file are written in uppercase.
+    ///
+    /// ## Errors
+    ///
+    /// Return an error if the file cannot be created or written to.
+    pub fn dump_with_original_case(&self, path: PathBuf) -> ParseResult<()> {
+        self.write(path, true)
+    }
+
+    fn write(&self, path: PathBuf, original_case: bool) -> ParseResult<()> {
         let mut file = File::create(path).map_err(|e| ParseError::FileError(e.to_string()))?;
-        for instruction in &self.instructions {
-            writeln!(file, "{instruction}").map_err(|e| ParseError::FileError(e.to_string()))?;
+        file.write_all(self.render(original_case).as_bytes())
+            .map_err(|e| ParseError::FileError(e.to_string()))
+    }
+
+    fn render(&self, original_case: bool) -> String {
+        let mut result = String::new();
+        let nodes = self.tree.nodes();
+
+        // formatted lines use the line break of the original text
+        let line_break = self.tree.newline();
+        let format = |text: String| {
+            if line_break == "\n" {
+                text
+            } else {
+                text.replace('\n', line_break)
+            }
+        };
+
+        // unmodified directives are written as in the original text
+        let original = self.tree.directives();
+        if self.directives == *original {
+            for node in nodes.iter().filter(|node| node.kind == NodeKind::Directive) {
+                result.push_str(&node.text);
+                result.push_str(&node.newline);
+            }
+        } else {
+            result.push_str(&format(self.directives.to_string()));
+        }
+
+        // the original text of the instructions is only valid with the original escape character
+        let escape = self.directives.escape_char();
+        let lossless = escape == original.escape_char();
+
+        let origins = self.origins();
+        let mut newline = line_break;
+        for (instruction, origin) in self.instructions.iter().zip(origins) {
+            // the last line of the original text might not end with a line break
+            if newline.is_empty() {
+                result.push_str(line_break);
+            }
+
+            // unmodified instructions are written as in the original text
+            if let Some(origin) = origin.filter(|o| lossless && o.instruction == *instruction) {
+                let node = self.node(origin);
+                result.push_str(&node.text);
+                result.push_str(&node.newline);
+                newline = &node.newline;
+                continue;
+            }
+
+            let mut line = instruction.with_escape(escape).to_string();
+            if original_case && let Some(origin) = origin {
+                let keywords = &origin.keywords;
+                // skip keywords of instructions that were replaced in the meantime
+                if keywords
+                    .first()
+                    .is_some_and(|keyword| starts_with_keyword(&line, keyword))
+                {
+                    line = restore_keywords(&line, keywords);
+                }
+            }
+            result.push_str(&format(line));
+            newline = line_break;
+            result.push_str(newline);
         }
-        Ok(())
+        result
+    }
+
+    /// Resolve the Dockerfile for the given build options, like `docker build` would.
+    ///
+    /// The build arguments override the defaults of the declared `ARG` instructions, and the
+    /// variables in the arguments of the instructions supporting environment replacement are
+    /// expanded. Global `ARG`s declared before the first `FROM` are only visible to `FROM`
+    /// instructions and to stages redeclaring them, `ENV` takes precedence over `ARG`, and a stage
+    /// based on an earlier stage inherits its environment. The triggers of `ONBUILD` are kept
+    /// unexpanded for the downstream build, and the stages following the target stage are left
+    /// out. A quoted or escaped dollar sign stays literal.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use std::str::FromStr;
+    ///
+    /// use dockerfile_parser_rs::BuildArgs;
+    /// use dockerfile_parser_rs::Dockerfile;
+    ///
+    /// let dockerfile = Dockerfile::from_str("ARG BASE=alpine\nFROM ${BASE}\n").unwrap();
+    /// let build = BuildArgs {
+    ///     args: HashMap::from([(String::from("BASE"), String::from("ubuntu:24.04"))]),
+    ///     ..BuildArgs::new()
+    /// };
+    ///
+    /// let dockerfile = dockerfile.evaluate(&build).unwrap();
+    /// assert_eq!(dockerfile.instructions[1].to_string(), "FROM ubuntu:24.04");
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// Return an error if the target stage does not exist or if a variable cannot be expanded.
+    pub fn evaluate(&self, build: &BuildArgs) -> ParseResult<Self> {
+        let text = self.tree.to_string();
+        let origins = self.origins();
+        let locate = |index: usize, error: ParseError| {
+            let Some(origin) = origins[index] else {
+                return error;
+            };
+            let error = match origin.keywords.first() {
+                Some(keyword) => error.with_instruction(keyword),
+                None => error,
+            };
+            error.with_span(&text, self.node(origin).span)
+        };
+
+        let instructions = evaluate_instructions(
+            &self.instructions,
+            build,
+            self.directives.escape_char(),
+            locate,
+        )?;
+
+        Ok(Self {
+            instructions,
+            ..self.clone()
+        })
     }
 
     /// Serialize the Dockerfile in JSON format.
@@ -122,102 +433,125 @@ impl Dockerfile {
 
     /// Return the number of instructions in the Dockerfile.
     pub fn steps(&self) -> usize {
-        self.instructions
-            .iter()
-            .filter(|i| !matches!(i, Instruction::Empty {} | Instruction::Comment { .. }))
-            .count()
+        count_steps(&self.instructions)
     }
 
     /// Return the number of layers in the Dockerfile.
     pub fn layers(&self) -> usize {
+        count_layers(&self.instructions)
+    }
+
+    /// Return the build stages of the Dockerfile in order.
+    pub fn stages(&self) -> Vec<Stage<'_>> {
+        stage_ranges(&self.instructions)
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, range)| Stage::new(index, &self.instructions[range]))
+            .collect()
+    }
+
+    /// Return the stage with the given name, or with the given index if the name is a number.
+    ///
+    /// Stage names are not case-sensitive.
+    pub fn stage(&self, name: &str) -> Option<Stage<'_>> {
+        let (index, range) = find_stage(&self.instructions, name)?;
+        Stage::new(index, &self.instructions[range])
+    }
+
+    /// Return a mutable view of the stage with the given name, or with the given index if the name
+    /// is a number.
+    pub fn stage_mut(&mut self, name: &str) -> Option<StageMut<'_>> {
+        let (index, range) = find_stage(&self.instructions, name)?;
+        Some(StageMut::new(index, range, &mut self.instructions))
+    }
+
+    /// Return the arguments declared in the global scope, before the first FROM instruction.
+    pub fn global_args(&self) -> impl Iterator<Item = &(String, Option<Word>)> {
         self.instructions
             .iter()
-            .filter(|i| {
-                matches!(
-                    i,
-                    Instruction::Add { .. } | Instruction::Copy { .. } | Instruction::Run { .. }
-                )
+            .take_while(|instruction| !matches!(instruction, Instruction::From { .. }))
+            .filter_map(|instruction| match instruction {
+                Instruction::Arg(args) => Some(args),
+                _ => None,
             })
-            .count()
+            .flatten()
     }
 
-    /// Return the number of stages in the Dockerfile.
-    pub fn stages(&self) -> usize {
-        self.instructions
-            .iter()
-            .filter(|i| matches!(i, Instruction::From { .. }))
-            .count()
+    /// Replace every deprecated `MAINTAINER` instruction with an equivalent `LABEL` instruction.
+    ///
+    /// The maintainer is stored under the `org.opencontainers.image.authors` key.
+    /// Return the number of replaced instructions.
+    pub fn migrate_maintainers(&mut self) -> usize {
+        let mut count = 0;
+        for instruction in &mut self.instructions {
+            if let Instruction::Maintainer { name } = instruction {
+                // the name of the maintainer is not expanded
+                *instruction = Instruction::Label(vec![(
+                    Word::from(MAINTAINER_LABEL),
+                    Word::literal(std::mem::take(name)),
+                )]);
+                count += 1;
+            }
+        }
+        count
     }
 }
 
-fn parse(lines: Vec<String>) -> ParseResult<Vec<Instruction>> {
-    let mut instructions = Vec::new();
+fn starts_with_keyword(line: &str, keyword: &str) -> bool {
+    line.split(SPACE)
+        .next()
+        .is_some_and(|word| word.eq_ignore_ascii_case(keyword))
+}
 
-    for line in lines {
-        // preserve empty lines
-        if line.is_empty() {
-            instructions.push(Instruction::Empty {});
-        // preserve comments
-        } else if line.starts_with(HASHTAG) {
-            instructions.push(Instruction::Comment(line.clone()));
-        } else {
-            let (instruction, arguments) = split_instruction_and_arguments(&line)?;
-            let instruction = match instruction.as_str() {
-                "ADD" => add::parse(&arguments),
-                "ARG" => Ok(arg::parse(&arguments)),
-                "CMD" => Ok(cmd::parse(&arguments)),
-                "COPY" => copy::parse(&arguments),
-                "ENTRYPOINT" => Ok(entrypoint::parse(&arguments)),
-                "ENV" => Ok(env::parse(&arguments)),
-                "EXPOSE" => Ok(expose::parse(arguments)),
-                "LABEL" => Ok(label::parse(&arguments)),
-                "FROM" => from::parse(&arguments),
-                "RUN" => run::parse(&arguments),
-                "SHELL" => shell::parse(&arguments),
-                "STOPSIGNAL" => stopsignal::parse(&arguments),
-                "USER" => user::parse(&arguments),
-                "VOLUME" => Ok(volume::parse(&arguments)),
-                "WORKDIR" => workdir::parse(&arguments),
-                _ => return Err(ParseError::UnknownInstruction(instruction)),
-            }?;
-            instructions.push(instruction);
+fn parse(node: &SyntaxNode, escape: char) -> ParseResult<Instruction> {
+    match node.kind {
+        // preserve empty lines and comments
+        NodeKind::Empty => Ok(Instruction::Empty {}),
+        NodeKind::Comment => Ok(Instruction::Comment(node.content().to_owned())),
+        NodeKind::Directive | NodeKind::Instruction => {
+            parse_instruction(node.content(), node.heredocs(), escape)
         }
     }
-    Ok(instructions)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ast::Command;
+    use crate::ast::Source;
+    use crate::identity::Identity;
+    use crate::image::ImageRef;
 
     fn mock_dummy_dockerfile() -> Dockerfile {
         let instructions = vec![
             Instruction::From {
                 platform: None,
-                image: String::from("docker.io/library/fedora:latest"),
+                image: "docker.io/library/fedora:latest".parse().unwrap(),
                 alias: Some(String::from("base")),
             },
             Instruction::Run {
-                mount: None,
+                mounts: Vec::new(),
                 network: None,
                 security: None,
-                command: vec![String::from("cat"), String::from("/etc/os-release")],
-                heredoc: None,
+                command: Command::Shell(String::from("cat /etc/os-release")),
+                heredocs: Vec::new(),
             },
             Instruction::From {
                 platform: None,
-                image: String::from("docker.io/library/ubuntu:latest"),
+                image: "docker.io/library/ubuntu:latest".parse().unwrap(),
                 alias: Some(String::from("builder")),
             },
             Instruction::Copy {
-                from: Some(String::from("base")),
+                from: Some(ImageRef::Stage(String::from("base"))),
                 chown: None,
                 chmod: None,
-                link: None,
-                sources: vec![String::from("file.txt")],
-                destination: String::from("/tmp/file.txt"),
+                exclude: Vec::new(),
+                link: false,
+                parents: false,
+                sources: vec![Source::Path(Word::from("file.txt"))],
+                destination: Word::from("/tmp/file.txt"),
             },
-            Instruction::Entrypoint(vec![String::from("/bin/bash")]),
+            Instruction::Entrypoint(Command::Exec(vec![String::from("/bin/bash")])),
         ];
 
         Dockerfile::new(instructions)
@@ -235,7 +569,226 @@ mod tests {
         let dockerfile = Dockerfile::from_str(&content).unwrap();
         assert_eq!(dockerfile.steps(), 5);
         assert_eq!(dockerfile.layers(), 2);
-        assert_eq!(dockerfile.stages(), 2);
+        assert_eq!(dockerfile.stages().len(), 2);
+    }
+
+    #[test]
+    fn test_dockerfile_from_str_with_lowercase_keywords() {
+        let content = "from docker.io/library/fedora:latest as base\nrun cat /etc/os-release\n";
+
+        let dockerfile = Dockerfile::from_str(content).unwrap();
+        assert_eq!(
+            dockerfile.instructions[0],
+            Instruction::From {
+                platform: None,
+                image: "docker.io/library/fedora:latest".parse().unwrap(),
+                alias: Some(String::from("base")),
+            }
+        );
+        assert_eq!(dockerfile.layers(), 1);
+    }
+
+    #[test]
+    fn test_dockerfile_spans() {
+        let mut content = String::new();
+        content.push_str("# syntax=docker/dockerfile:1\r\n");
+        content.push_str("FROM docker.io/library/fedora:latest\r\n");
+        content.push_str("RUN dnf upgrade -y && \\\r\n");
+        content.push_str("    dnf clean all\r\n");
+        content.push_str("RUN <<EOF\r\n");
+        content.push_str("cat /etc/os-release\r\n");
+        content.push_str("EOF\r\n");
+
+        let dockerfile = Dockerfile::from_str(&content).unwrap();
+        let spans: Vec<Span> = dockerfile
+            .spanned()
+            .map(|(span, _)| span.unwrap())
+            .collect();
+
+        assert_eq!(spans.len(), 3);
+        assert_eq!((spans[0].start_line, spans[0].end_line), (2, 2));
+        assert_eq!((spans[1].start_line, spans[1].end_line), (3, 4));
+        assert_eq!((spans[2].start_line, spans[2].end_line), (5, 7));
+        assert_eq!(
+            spans[1].slice(&content),
+            Some("RUN dnf upgrade -y && \\\r\n    dnf clean all")
+        );
+        assert_eq!(
+            spans[2].slice(&content),
+            Some("RUN <<EOF\r\ncat /etc/os-release\r\nEOF")
+        );
+        assert_eq!(mock_dummy_dockerfile().span(0), None);
+    }
+
+    #[test]
+    fn test_dockerfile_spans_follow_instructions() {
+        let content = "FROM scratch\nUSER app\nWORKDIR /app\n";
+        let mut dockerfile = Dockerfile::from_str(content).unwrap();
+
+        dockerfile
+            .instructions
+            .insert(0, Instruction::Comment(String::from("# base")));
+        dockerfile.instructions.remove(2);
+        dockerfile.instructions.push(Instruction::User {
+            user: Identity::Name(String::from("root")),
+            group: None,
+        });
+
+        let lines: Vec<Option<usize>> = dockerfile
+            .spanned()
+            .map(|(span, _)| span.map(|span| span.start_line))
+            .collect();
+        assert_eq!(lines, vec![None, Some(1), Some(3), None]);
+    }
+
+    #[test]
+    fn test_dockerfile_spans_after_vec_edits() {
+        let content = "FROM scratch\nUSER app\nWORKDIR /app\nCMD run\n";
+        let original = Dockerfile::from_str(content).unwrap();
+
+        let mut dockerfile = original.clone();
+        dockerfile
+            .instructions
+            .insert(0, Instruction::Comment(String::from("# base")));
+        dockerfile.instructions[4] = Instruction::Cmd(Command::Shell(String::from("test")));
+        dockerfile
+            .instructions
+            .retain(|i| !matches!(i, Instruction::User { .. }));
+
+        assert_eq!(dockerfile.span(0), None);
+        assert_eq!(dockerfile.span(1), original.span(0));
+        assert_eq!(dockerfile.span(2), original.span(2));
+        assert_eq!(dockerfile.span(3), original.span(3));
+
+        // a moved instruction never gets the span of another one
+        let mut dockerfile = original.clone();
+        dockerfile.instructions.swap(1, 2);
+        assert_eq!(dockerfile.span(0), original.span(0));
+        assert!([None, original.span(2)].contains(&dockerfile.span(1)));
+        assert!([None, original.span(1)].contains(&dockerfile.span(2)));
+        assert_eq!(dockerfile.span(3), original.span(3));
+    }
+
+    #[test]
+    fn test_dockerfile_evaluation_error_location_after_edits() {
+        let content = "FROM scratch\nWORKDIR /app\nUSER ${NAME?}\n";
+        let mut dockerfile = Dockerfile::from_str(content).unwrap();
+        dockerfile
+            .instructions
+            .insert(0, Instruction::Comment(String::from("# base")));
+
+        let error = dockerfile.evaluate(&BuildArgs::new()).unwrap_err();
+        let details = error.details().unwrap();
+
+        assert_eq!(details.instruction, Some(String::from("USER")));
+        assert_eq!(details.line, Some(3));
+    }
+
+    #[test]
+    fn test_dockerfile_error_location() {
+        let mut content = String::new();
+        content.push_str("FROM docker.io/library/fedora:latest\n");
+        content.push('\n');
+        content.push_str("HEALTHCHECK --interval=30s \\\n");
+        content.push_str("    --retries=often CMD true\n");
+
+        let error = Dockerfile::from_str(&content).unwrap_err();
+        let details = error.details().unwrap();
+
+        assert!(matches!(error, ParseError::SyntaxError(_)));
+        assert_eq!(details.instruction, Some(String::from("HEALTHCHECK")));
+        assert_eq!(details.found, Some(String::from("often")));
+        assert_eq!(details.line, Some(4));
+        assert_eq!(details.column, Some(15));
+    }
+
+    #[test]
+    fn test_dockerfile_invalid_value_location() {
+        let content = "FROM scratch\nUSER app\nSTOPSIGNAL SIGNONE\n";
+
+        let error = Dockerfile::from_str(content).unwrap_err();
+        let details = error.details().unwrap();
+
+        assert_eq!(details.instruction, Some(String::from("STOPSIGNAL")));
+        assert_eq!(details.line, Some(3));
+        assert_eq!(details.column, Some(12));
+    }
+
+    #[test]
+    fn test_dockerfile_parse_tolerant() {
+        let mut content = String::new();
+        content.push_str("# escape=/\n");
+        content.push_str("FROM docker.io/library/fedora:latest\n");
+        content.push_str("MAKE love \\\n");
+        content.push_str("    not war\n");
+        content.push_str("RUN cat /etc/os-release\n");
+        content.push_str("STOPSIGNAL\n");
+
+        let (dockerfile, errors) = Dockerfile::parse_tolerant(&content);
+
+        assert_eq!(errors.len(), 3);
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| e.details().unwrap().line.unwrap())
+                .collect::<Vec<_>>(),
+            vec![1, 3, 6]
+        );
+        assert_eq!(dockerfile.instructions.len(), 5);
+        assert_eq!(
+            dockerfile.instructions[2],
+            Instruction::Invalid(String::from("MAKE love \\\n    not war"))
+        );
+        assert_eq!(dockerfile.steps(), 2);
+    }
+
+    #[test]
+    fn test_dockerfile_render_keeps_unmodified_instructions() {
+        let content = "FROM  scratch\nENV B=2 \\\n    A=1\nUSER 1001";
+        let mut dockerfile = Dockerfile::from_str(content).unwrap();
+
+        assert_eq!(dockerfile.render(false), content);
+
+        dockerfile
+            .instructions
+            .insert(0, Instruction::Comment(String::from("# base")));
+        dockerfile.instructions.remove(1);
+        dockerfile.instructions.push(Instruction::Workdir {
+            path: "/app".parse().unwrap(),
+        });
+
+        let expected = "# base\nENV B=2 \\\n    A=1\nUSER 1001\nWORKDIR /app\n";
+        assert_eq!(dockerfile.render(false), expected);
+    }
+
+    #[test]
+    fn test_dockerfile_render_keeps_keywords_after_edits() {
+        let content = "from scratch\nuser app\nworkdir /app\n";
+        let mut dockerfile = Dockerfile::from_str(content).unwrap();
+
+        dockerfile.instructions.insert(
+            1,
+            Instruction::User {
+                user: Identity::Id(0),
+                group: None,
+            },
+        );
+        dockerfile.instructions[3] = Instruction::Workdir {
+            path: "/src".parse().unwrap(),
+        };
+
+        let expected = "from scratch\nUSER 0\nuser app\nworkdir /src\n";
+        assert_eq!(dockerfile.render(true), expected);
+    }
+
+    #[test]
+    fn test_dockerfile_render_with_new_escape() {
+        let content = "FROM scratch\nRUN echo \\\n    hello\n";
+        let mut dockerfile = Dockerfile::from_str(content).unwrap();
+        dockerfile.directives.escape = Some('`');
+
+        let expected = "# escape=`\nFROM scratch\nRUN echo hello\n";
+        assert_eq!(dockerfile.render(false), expected);
     }
 
     #[test]
@@ -253,6 +806,77 @@ mod tests {
     #[test]
     fn test_dockerfile_stages() {
         let dockerfile = mock_dummy_dockerfile();
-        assert_eq!(dockerfile.stages(), 2);
+        assert_eq!(dockerfile.stages().len(), 2);
+    }
+
+    #[test]
+    fn test_dockerfile_resolves_stage_references() {
+        let mut content = String::new();
+        content.push_str("FROM builder AS base\n");
+        content.push_str("FROM rust:1.85 AS Builder\n");
+        content.push_str("FROM builder\n");
+        content.push_str("COPY --from=0 /a /a\n");
+        content.push_str("COPY --from=alpine /b /b\n");
+
+        let dockerfile = Dockerfile::from_str(&content).unwrap();
+        let images: Vec<&ImageRef> = dockerfile
+            .instructions
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::From { image, .. } => Some(image),
+                Instruction::Copy { from, .. } => from.as_ref(),
+                _ => None,
+            })
+            .collect();
+
+        // a stage can only refer to earlier stages
+        assert!(matches!(images[0], ImageRef::Image(_)));
+        assert!(matches!(images[1], ImageRef::Image(_)));
+        assert_eq!(images[2], &ImageRef::Stage(String::from("builder")));
+        assert_eq!(images[3], &ImageRef::Stage(String::from("0")));
+        assert!(matches!(images[4], ImageRef::Image(_)));
+    }
+
+    #[test]
+    fn test_dockerfile_directives() {
+        let mut content = String::new();
+        content.push_str("# syntax=docker/dockerfile:1\n");
+        content.push_str("# check=error=true\n");
+        content.push('\n');
+        content.push_str("# escape=`\n");
+        content.push_str("FROM docker.io/library/fedora:latest\n");
+
+        let dockerfile = Dockerfile::from_str(&content).unwrap();
+        assert_eq!(
+            dockerfile.directives.syntax,
+            Some(String::from("docker/dockerfile:1"))
+        );
+        assert_eq!(
+            dockerfile.directives.check,
+            Some(String::from("error=true"))
+        );
+        // directives after an empty line are plain comments
+        assert_eq!(dockerfile.directives.escape, None);
+        assert_eq!(
+            dockerfile.instructions[1],
+            Instruction::Comment(String::from("# escape=`"))
+        );
+    }
+
+    #[test]
+    fn test_dockerfile_migrate_maintainers() {
+        let mut dockerfile = Dockerfile::from_str(
+            "FROM docker.io/library/fedora:latest\nMAINTAINER John Doe <john.doe@example.com>\n",
+        )
+        .unwrap();
+
+        assert_eq!(dockerfile.migrate_maintainers(), 1);
+        assert_eq!(
+            dockerfile.instructions[1],
+            Instruction::Label(vec![(
+                Word::from("org.opencontainers.image.authors"),
+                Word::from("John Doe <john.doe@example.com>"),
+            )])
+        );
     }
 }
@@ -0,0 +1,128 @@
+// https://docs.docker.com/reference/dockerfile/#user
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::ParseResult;
+use crate::error::ErrorDetails;
+use crate::error::ParseError;
+use crate::symbols::chars::COLON;
+use crate::symbols::chars::DOLLAR;
+use crate::symbols::chars::LEFT_BRACE;
+use crate::symbols::chars::RIGHT_BRACE;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// This enum represents the user or the group of the USER instruction.
+///
+/// ### Example
+///
+/// ```
+/// use dockerfile_parser_rs::Identity;
+///
+/// assert_eq!("1001".parse::<Identity>().unwrap(), Identity::Id(1001));
+/// assert_eq!(
+///     "app".parse::<Identity>().unwrap(),
+///     Identity::Name(String::from("app"))
+/// );
+/// ```
+pub enum Identity {
+    /// A numeric user or group id.
+    Id(u32),
+    /// A user or group name.
+    Name(String),
+    /// A user or group with variables that are not expanded yet, as written.
+    Variable(String),
+}
+
+impl FromStr for Identity {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.contains(DOLLAR) {
+            return Ok(Self::Variable(s.to_owned()));
+        }
+
+        let invalid =
+            |expected: &str| ParseError::SyntaxError(Box::new(ErrorDetails::expected(expected, s)));
+
+        if s.chars().all(|c| c.is_ascii_digit()) && !s.is_empty() {
+            return s
+                .parse()
+                .map(Self::Id)
+                .map_err(|_| invalid("a numeric id between 0 and 4294967295"));
+        }
+        if s.is_empty() || s.contains(|c: char| c == COLON || c.is_whitespace()) {
+            return Err(invalid("a user or group name, or a numeric id"));
+        }
+        Ok(Self::Name(s.to_owned()))
+    }
+}
+
+impl fmt::Display for Identity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Id(id) => write!(f, "{id}"),
+            Self::Name(value) | Self::Variable(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+/// Split the value of the USER instruction into the user and the optional group.
+///
+/// A colon inside a `${...}` substitution does not separate the group.
+pub fn parse_user(value: &str) -> ParseResult<(Identity, Option<Identity>)> {
+    let mut depth = 0;
+    let mut previous = None;
+    let separator = value.char_indices().find(|(_, c)| {
+        match *c {
+            LEFT_BRACE if previous == Some(DOLLAR) => depth += 1,
+            RIGHT_BRACE if depth > 0 => depth -= 1,
+            _ => {}
+        }
+        previous = Some(*c);
+        *c == COLON && depth == 0
+    });
+
+    match separator {
+        Some((index, _)) => Ok((value[..index].parse()?, Some(value[index + 1..].parse()?))),
+        None => Ok((value.parse()?, None)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_user_with_group() {
+        assert_eq!(
+            parse_user("app:1001").unwrap(),
+            (
+                Identity::Name(String::from("app")),
+                Some(Identity::Id(1001))
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_user_with_variable_default() {
+        assert_eq!(
+            parse_user("${USER:-app}:$GROUP").unwrap(),
+            (
+                Identity::Variable(String::from("${USER:-app}")),
+                Some(Identity::Variable(String::from("$GROUP")))
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_user_invalid() {
+        for value in ["", "app:", ":staff", "app:staff:wheel", "99999999999"] {
+            let result = parse_user(value);
+            assert!(matches!(result, Err(ParseError::SyntaxError(_))), "{value}");
+        }
+    }
+}
@@ -0,0 +1,354 @@
+// https://github.com/distribution/reference/blob/main/reference.go
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::error::ErrorDetails;
+use crate::error::ParseError;
+use crate::symbols::chars::AT;
+use crate::symbols::chars::COLON;
+use crate::symbols::chars::DOLLAR;
+use crate::symbols::chars::SLASH;
+
+const DEFAULT_REGISTRY: &str = "docker.io";
+const LEGACY_DEFAULT_REGISTRY: &str = "index.docker.io";
+const OFFICIAL_NAMESPACE: &str = "library";
+const DEFAULT_TAG: &str = "latest";
+const SCRATCH: &str = "scratch";
+
+static REGISTRY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:[a-zA-Z0-9](?:[a-zA-Z0-9-]*[a-zA-Z0-9])?(?:\.[a-zA-Z0-9](?:[a-zA-Z0-9-]*[a-zA-Z0-9])?)*|\[[a-fA-F0-9:]+\])(?::[0-9]+)?$").unwrap()
+});
+static PATH: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^[a-z0-9]+(?:(?:[._]|__|-+)[a-z0-9]+)*(?:/[a-z0-9]+(?:(?:[._]|__|-+)[a-z0-9]+)*)*$",
+    )
+    .unwrap()
+});
+static TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\w[\w.-]{0,127}$").unwrap());
+static DIGEST: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[A-Za-z][A-Za-z0-9]*(?:[-_+.][A-Za-z][A-Za-z0-9]*)*:[0-9a-fA-F]{32,}$").unwrap()
+});
+// https://github.com/moby/buildkit/blob/master/frontend/dockerfile/instructions/parse.go
+static STAGE_NAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[a-zA-Z][a-zA-Z0-9_.-]*$").unwrap());
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// This enum represents the image a build stage starts from, or the image `COPY --from` copies
+/// from.
+///
+/// ### Example
+///
+/// ```
+/// use dockerfile_parser_rs::ImageRef;
+///
+/// let image: ImageRef = "registry.example.com:5000/team/app:1.0".parse().unwrap();
+/// let ImageRef::Image(image) = image else {
+///     unreachable!();
+/// };
+///
+/// assert_eq!(image.registry.as_deref(), Some("registry.example.com:5000"));
+/// assert_eq!(image.path, "team/app");
+/// assert_eq!(image.tag.as_deref(), Some("1.0"));
+///
+/// let ubuntu: ImageRef = "ubuntu".parse().unwrap();
+/// assert_eq!(ubuntu.normalized().unwrap(), "docker.io/library/ubuntu:latest");
+/// ```
+pub enum ImageRef {
+    /// The empty `scratch` image.
+    Scratch,
+    /// A build stage, referenced by its name, or by its index for `COPY --from`.
+    Stage(String),
+    /// A reference with variables that are not expanded yet, as written.
+    Variable(String),
+    /// An image of a registry.
+    Image(Image),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// This struct represents an image reference as written, e.g. `quay.io/fedora/fedora:41`.
+pub struct Image {
+    /// The registry host with an optional port, if it was given.
+    pub registry: Option<String>,
+    /// The path of the repository in the registry, e.g. `fedora/fedora`.
+    pub path: String,
+    pub tag: Option<String>,
+    /// The content digest, e.g. `sha256:...`.
+    pub digest: Option<String>,
+}
+
+impl ImageRef {
+    /// Return the fully qualified reference of a registry image, e.g.
+    /// `docker.io/library/ubuntu:latest`.
+    pub fn normalized(&self) -> Option<String> {
+        match self {
+            Self::Image(image) => Some(image.normalized()),
+            _ => None,
+        }
+    }
+
+    /// Turn a plain name of one of the given earlier stages into a stage reference.
+    ///
+    /// Stage names are case-insensitive. If the index is allowed, a number refers to a stage too.
+    pub(crate) fn resolve_stage(&mut self, stages: &[Option<String>], index: bool) {
+        let Self::Image(image) = self else {
+            return;
+        };
+        if image.registry.is_some() || image.tag.is_some() || image.digest.is_some() {
+            return;
+        }
+
+        let named = stages
+            .iter()
+            .flatten()
+            .any(|name| name.eq_ignore_ascii_case(&image.path));
+        let indexed = index
+            && image
+                .path
+                .parse::<usize>()
+                .is_ok_and(|index| index < stages.len());
+
+        if named || indexed {
+            *self = Self::Stage(std::mem::take(&mut image.path));
+        }
+    }
+}
+
+impl Image {
+    /// Return the registry of the image, `docker.io` if none was given.
+    pub fn domain(&self) -> &str {
+        match self.registry.as_deref() {
+            None | Some(LEGACY_DEFAULT_REGISTRY) => DEFAULT_REGISTRY,
+            Some(registry) => registry,
+        }
+    }
+
+    /// Return the path of the repository, including the `library` namespace of official images.
+    pub fn repository(&self) -> String {
+        if self.domain() == DEFAULT_REGISTRY && !self.path.contains(SLASH) {
+            format!("{OFFICIAL_NAMESPACE}{SLASH}{}", self.path)
+        } else {
+            self.path.clone()
+        }
+    }
+
+    /// Return the fully qualified reference, with the `latest` tag if neither a tag nor a digest
+    /// was given.
+    pub fn normalized(&self) -> String {
+        let image = Self {
+            registry: Some(self.domain().to_owned()),
+            path: self.repository(),
+            tag: self.tag.clone(),
+            digest: self.digest.clone(),
+        };
+
+        if image.tag.is_none() && image.digest.is_none() {
+            format!("{image}{COLON}{DEFAULT_TAG}")
+        } else {
+            image.to_string()
+        }
+    }
+}
+
+impl FromStr for ImageRef {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == SCRATCH {
+            return Ok(Self::Scratch);
+        }
+        if s.contains(DOLLAR) {
+            return Ok(Self::Variable(s.to_owned()));
+        }
+
+        let invalid = |part: &str, value: &str| {
+            ParseError::SyntaxError(Box::new(ErrorDetails {
+                found: Some(s.to_owned()),
+                ..ErrorDetails::new(format!("invalid {part} {value} in image reference {s}"))
+            }))
+        };
+
+        let (name, digest) = match s.split_once(AT) {
+            Some((name, digest)) => (name, Some(digest)),
+            None => (s, None),
+        };
+        let (name, tag) = match name.rsplit_once(COLON) {
+            Some((name, tag)) if !tag.contains(SLASH) => (name, Some(tag)),
+            _ => (name, None),
+        };
+        // the first component is a registry if it looks like a host name
+        let (registry, path) = match name.split_once(SLASH) {
+            Some((first, path))
+                if first.contains(['.', COLON])
+                    || first == "localhost"
+                    || first != first.to_lowercase() =>
+            {
+                (Some(first), path)
+            }
+            _ => (None, name),
+        };
+
+        if let Some(registry) = registry.filter(|registry| !REGISTRY.is_match(registry)) {
+            return Err(invalid("registry", registry));
+        }
+        if !PATH.is_match(path) {
+            return Err(invalid("repository", path));
+        }
+        if let Some(tag) = tag.filter(|tag| !TAG.is_match(tag)) {
+            return Err(invalid("tag", tag));
+        }
+        if let Some(digest) = digest.filter(|digest| !DIGEST.is_match(digest)) {
+            return Err(invalid("digest", digest));
+        }
+
+        Ok(Self::Image(Image {
+            registry: registry.map(String::from),
+            path: path.to_owned(),
+            tag: tag.map(String::from),
+            digest: digest.map(String::from),
+        }))
+    }
+}
+
+/// Parse the image reference, keeping a name that is only valid for a stage as a stage reference.
+pub fn parse_image_or_stage(value: &str) -> Result<ImageRef, ParseError> {
+    value.parse().or_else(|error| {
+        if STAGE_NAME.is_match(value) {
+            Ok(ImageRef::Stage(value.to_owned()))
+        } else {
+            Err(error)
+        }
+    })
+}
+
+impl fmt::Display for ImageRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Scratch => write!(f, "{SCRATCH}"),
+            Self::Stage(name) => write!(f, "{name}"),
+            Self::Variable(reference) => write!(f, "{reference}"),
+            Self::Image(image) => write!(f, "{image}"),
+        }
+    }
+}
+
+impl fmt::Display for Image {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(registry) = &self.registry {
+            write!(f, "{registry}{SLASH}")?;
+        }
+        write!(f, "{}", self.path)?;
+        if let Some(tag) = &self.tag {
+            write!(f, "{COLON}{tag}")?;
+        }
+        if let Some(digest) = &self.digest {
+            write!(f, "{AT}{digest}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGEST: &str = "sha256:7c3b1b2e5f3d4b3a1a9e1c0f8e6d5c4b3a291807f6e5d4c3b2a1908f7e6d5c4b";
+
+    fn image(s: &str) -> Image {
+        match s.parse::<ImageRef>().unwrap() {
+            ImageRef::Image(image) => image,
+            other => panic!("expected an image, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_image() {
+        let reference = format!("localhost:5000/team/app:1.0@{DIGEST}");
+        let parsed = image(&reference);
+
+        assert_eq!(
+            parsed,
+            Image {
+                registry: Some(String::from("localhost:5000")),
+                path: String::from("team/app"),
+                tag: Some(String::from("1.0")),
+                digest: Some(String::from(DIGEST)),
+            }
+        );
+        assert_eq!(parsed.to_string(), reference);
+    }
+
+    #[test]
+    fn test_parse_image_without_registry() {
+        let parsed = image("bitnami/redis:7.4");
+
+        assert_eq!(parsed.registry, None);
+        assert_eq!(parsed.path, "bitnami/redis");
+        assert_eq!(parsed.normalized(), "docker.io/bitnami/redis:7.4");
+    }
+
+    #[test]
+    fn test_normalized_official_image() {
+        assert_eq!(
+            image("ubuntu").normalized(),
+            "docker.io/library/ubuntu:latest"
+        );
+        assert_eq!(
+            image("index.docker.io/ubuntu:24.04").normalized(),
+            "docker.io/library/ubuntu:24.04"
+        );
+        assert_eq!(
+            image(&format!("ubuntu@{DIGEST}")).normalized(),
+            format!("docker.io/library/ubuntu@{DIGEST}")
+        );
+    }
+
+    #[test]
+    fn test_parse_scratch_and_variables() {
+        assert_eq!("scratch".parse::<ImageRef>().unwrap(), ImageRef::Scratch);
+        assert_eq!(
+            "${BASE}:${TAG}".parse::<ImageRef>().unwrap(),
+            ImageRef::Variable(String::from("${BASE}:${TAG}"))
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_image_fails() {
+        for reference in ["Ubuntu", "ubuntu:", "ubuntu@sha256:abc", "my..app", "-app"] {
+            assert!(reference.parse::<ImageRef>().is_err(), "{reference}");
+        }
+    }
+
+    #[test]
+    fn test_parse_image_or_stage() {
+        assert_eq!(
+            parse_image_or_stage("Builder").unwrap(),
+            ImageRef::Stage(String::from("Builder"))
+        );
+        assert!(parse_image_or_stage("ubuntu:").is_err());
+    }
+
+    #[test]
+    fn test_resolve_stage() {
+        let stages = [None, Some(String::from("Builder"))];
+
+        let mut named: ImageRef = "builder".parse().unwrap();
+        named.resolve_stage(&stages, false);
+        let mut tagged: ImageRef = "builder:latest".parse().unwrap();
+        tagged.resolve_stage(&stages, false);
+        let mut indexed: ImageRef = "1".parse().unwrap();
+        indexed.resolve_stage(&stages, true);
+        let mut unknown: ImageRef = "2".parse().unwrap();
+        unknown.resolve_stage(&stages, true);
+
+        assert_eq!(named, ImageRef::Stage(String::from("builder")));
+        assert!(matches!(tagged, ImageRef::Image(_)));
+        assert_eq!(indexed, ImageRef::Stage(String::from("1")));
+        assert!(matches!(unknown, ImageRef::Image(_)));
+    }
+}
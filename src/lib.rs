@@ -1,15 +1,62 @@
+// some of the original tests predate the pedantic lints
+#![cfg_attr(
+    test,
+    allow(clippy::manual_string_new, clippy::semicolon_if_nothing_returned)
+)]
+
 mod ast;
+mod directives;
 mod error;
+mod evaluation;
 mod file;
+mod identity;
+mod image;
+mod mount;
 mod parser;
+mod platform;
+mod port;
 mod quoter;
+mod signal;
+mod span;
+mod stage;
+mod substitution;
 mod symbols;
+mod syntax;
 mod utils;
+mod word;
+mod workdir;
 
 // public API
 
 pub type ParseResult<T> = Result<T, ParseError>;
 
+pub use crate::ast::Command;
+pub use crate::ast::EscapedInstruction;
+pub use crate::ast::Healthcheck;
+pub use crate::ast::Heredoc;
 pub use crate::ast::Instruction;
+pub use crate::ast::Source;
+pub use crate::directives::Directives;
+pub use crate::error::ErrorDetails;
 pub use crate::error::ParseError;
+pub use crate::evaluation::BuildArgs;
 pub use crate::file::Dockerfile;
+pub use crate::identity::Identity;
+pub use crate::image::Image;
+pub use crate::image::ImageRef;
+pub use crate::mount::Mount;
+pub use crate::mount::Sharing;
+pub use crate::platform::Platform;
+pub use crate::port::PortSpec;
+pub use crate::port::Protocol;
+pub use crate::signal::Signal;
+pub use crate::span::Span;
+pub use crate::stage::FromSpec;
+pub use crate::stage::Stage;
+pub use crate::stage::StageMut;
+pub use crate::substitution::substitute;
+pub use crate::syntax::NodeKind;
+pub use crate::syntax::SyntaxNode;
+pub use crate::syntax::SyntaxTree;
+pub use crate::word::Word;
+pub use crate::workdir::WorkdirPath;
@@ -1,5 +1,6 @@
 use std::fs;
 use std::path::PathBuf;
+use std::process::ExitCode;
 
 use clap::Parser;
 use dockerfile_parser_rs::Dockerfile;
@@ -16,7 +17,17 @@ struct Args {
     output: Option<PathBuf>,
 }
 
-fn main() -> ParseResult<()> {
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprint!("{}", e.render());
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> ParseResult<()> {
     let args = Args::parse();
     let dockerfile = Dockerfile::from(args.path)?;
     let json = dockerfile.to_json()?;
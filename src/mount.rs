@@ -0,0 +1,648 @@
+// https://docs.docker.com/reference/dockerfile/#run---mount
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::error::ErrorDetails;
+use crate::error::ParseError;
+use crate::symbols::chars::COMMA;
+use crate::symbols::chars::DOUBLE_QUOTE;
+use crate::symbols::chars::EQUALS;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// This enum represents a filesystem mount of the RUN instruction.
+///
+/// A mount is parsed from the value of a `--mount` option and printed back in canonical form,
+/// with the type first and the fields in a fixed order.
+///
+/// ### Example
+///
+/// ```
+/// use dockerfile_parser_rs::Mount;
+///
+/// let mount: Mount = "target=/root/.cache,type=cache,sharing=locked".parse().unwrap();
+///
+/// assert_eq!(
+///     mount.to_string(),
+///     "type=cache,target=/root/.cache,sharing=locked"
+/// );
+/// ```
+pub enum Mount {
+    /// Bind files or directories of the build context or of another stage.
+    Bind {
+        target: String,
+        source: Option<String>,
+        from: Option<String>,
+        /// Bind mounts are read-only unless `rw` is given.
+        readonly: bool,
+    },
+    /// Mount a directory that persists between builds.
+    Cache {
+        target: String,
+        id: Option<String>,
+        sharing: Option<Sharing>,
+        source: Option<String>,
+        from: Option<String>,
+        readonly: bool,
+        mode: Option<u32>,
+        uid: Option<u32>,
+        gid: Option<u32>,
+    },
+    /// Mount a temporary filesystem.
+    Tmpfs {
+        target: String,
+        /// The size limit in bytes.
+        size: Option<u64>,
+    },
+    /// Mount a secret file, or expose it as an environment variable.
+    Secret {
+        id: Option<String>,
+        target: Option<String>,
+        env: Option<String>,
+        required: bool,
+        mode: Option<u32>,
+        uid: Option<u32>,
+        gid: Option<u32>,
+    },
+    /// Expose the SSH agent socket.
+    Ssh {
+        id: Option<String>,
+        target: Option<String>,
+        required: bool,
+        mode: Option<u32>,
+        uid: Option<u32>,
+        gid: Option<u32>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// This enum represents how a cache mount is shared between concurrent builds.
+pub enum Sharing {
+    Shared,
+    Private,
+    Locked,
+}
+
+/// The fields of a mount before its type is known.
+#[derive(Default)]
+struct Fields {
+    target: Option<String>,
+    source: Option<String>,
+    from: Option<String>,
+    id: Option<String>,
+    sharing: Option<Sharing>,
+    env: Option<String>,
+    readonly: Option<bool>,
+    required: bool,
+    mode: Option<u32>,
+    uid: Option<u32>,
+    gid: Option<u32>,
+    size: Option<u64>,
+}
+
+impl FromStr for Mount {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut kind = String::from("bind");
+        let mut keys = Vec::new();
+        let mut fields = Fields::default();
+
+        for field in split_fields(s)?.iter().filter(|field| !field.is_empty()) {
+            let (key, value) = match field.split_once(EQUALS) {
+                Some((key, value)) => (key.to_lowercase(), Some(value)),
+                None => (field.to_lowercase(), None),
+            };
+            if key == "type" {
+                kind = value.ok_or_else(|| invalid(&key, field))?.to_lowercase();
+                continue;
+            }
+            keys.push(fields.set(&key, value)?);
+        }
+
+        let allowed: &[&str] = match kind.as_str() {
+            "bind" => &["target", "source", "from", "readonly"],
+            "cache" => &[
+                "target", "id", "sharing", "source", "from", "readonly", "mode", "uid", "gid",
+            ],
+            "tmpfs" => &["target", "size"],
+            "secret" => &["id", "target", "env", "required", "mode", "uid", "gid"],
+            "ssh" => &["id", "target", "required", "mode", "uid", "gid"],
+            _ => {
+                return Err(ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+                    "one of the mount types bind, cache, tmpfs, secret or ssh",
+                    kind,
+                ))));
+            }
+        };
+
+        if let Some(key) = keys.iter().find(|key| !allowed.contains(key)) {
+            return Err(ParseError::SyntaxError(Box::new(ErrorDetails {
+                found: Some((*key).to_owned()),
+                ..ErrorDetails::new(format!("{key} isn't supported by {kind} mounts"))
+            })));
+        }
+
+        fields.into_mount(&kind)
+    }
+}
+
+impl Fields {
+    /// Set the field of the key, returning the canonical name of the key.
+    fn set(&mut self, key: &str, value: Option<&str>) -> Result<&'static str, ParseError> {
+        let required_value = || value.ok_or_else(|| invalid(key, key));
+
+        let key = match key {
+            "target" | "dst" | "destination" => {
+                self.target = Some(required_value()?.to_owned());
+                "target"
+            }
+            "source" | "src" => {
+                self.source = Some(required_value()?.to_owned());
+                "source"
+            }
+            "from" => {
+                self.from = Some(required_value()?.to_owned());
+                "from"
+            }
+            "id" => {
+                self.id = Some(required_value()?.to_owned());
+                "id"
+            }
+            "env" => {
+                self.env = Some(required_value()?.to_owned());
+                "env"
+            }
+            "sharing" => {
+                self.sharing = Some(required_value()?.parse()?);
+                "sharing"
+            }
+            "readonly" | "ro" => {
+                self.readonly = Some(parse_bool(key, value)?);
+                "readonly"
+            }
+            "rw" | "readwrite" => {
+                self.readonly = Some(!parse_bool(key, value)?);
+                "readonly"
+            }
+            "required" => {
+                self.required = parse_bool(key, value)?;
+                "required"
+            }
+            "mode" => {
+                let value = required_value()?;
+                let mode = u32::from_str_radix(value, 8).map_err(|_| invalid(key, value))?;
+                self.mode = Some(mode);
+                "mode"
+            }
+            "uid" => {
+                let value = required_value()?;
+                self.uid = Some(value.parse().map_err(|_| invalid(key, value))?);
+                "uid"
+            }
+            "gid" => {
+                let value = required_value()?;
+                self.gid = Some(value.parse().map_err(|_| invalid(key, value))?);
+                "gid"
+            }
+            "size" => {
+                let value = required_value()?;
+                self.size = Some(parse_size(value).ok_or_else(|| invalid(key, value))?);
+                "size"
+            }
+            _ => {
+                return Err(ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+                    "a known mount option",
+                    key,
+                ))));
+            }
+        };
+        Ok(key)
+    }
+
+    fn into_mount(self, kind: &str) -> Result<Mount, ParseError> {
+        let target = || {
+            self.target.clone().ok_or_else(|| {
+                ParseError::MissingArgument(Box::new(ErrorDetails::new(format!(
+                    "{kind} mounts require a target"
+                ))))
+            })
+        };
+
+        let mount = match kind {
+            "bind" => Mount::Bind {
+                target: target()?,
+                source: self.source,
+                from: self.from,
+                readonly: self.readonly.unwrap_or(true),
+            },
+            "cache" => Mount::Cache {
+                target: target()?,
+                id: self.id,
+                sharing: self.sharing,
+                source: self.source,
+                from: self.from,
+                readonly: self.readonly.unwrap_or(false),
+                mode: self.mode,
+                uid: self.uid,
+                gid: self.gid,
+            },
+            "tmpfs" => Mount::Tmpfs {
+                target: target()?,
+                size: self.size,
+            },
+            "secret" => {
+                if self.id.is_none() && self.target.is_none() {
+                    return Err(ParseError::MissingArgument(Box::new(ErrorDetails::new(
+                        "secret mounts require an id or a target",
+                    ))));
+                }
+                Mount::Secret {
+                    id: self.id,
+                    target: self.target,
+                    env: self.env,
+                    required: self.required,
+                    mode: self.mode,
+                    uid: self.uid,
+                    gid: self.gid,
+                }
+            }
+            _ => Mount::Ssh {
+                id: self.id,
+                target: self.target,
+                required: self.required,
+                mode: self.mode,
+                uid: self.uid,
+                gid: self.gid,
+            },
+        };
+        Ok(mount)
+    }
+}
+
+impl FromStr for Sharing {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "shared" => Ok(Self::Shared),
+            "private" => Ok(Self::Private),
+            "locked" => Ok(Self::Locked),
+            _ => Err(ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+                "one of shared, private or locked",
+                s,
+            )))),
+        }
+    }
+}
+
+impl fmt::Display for Mount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut fields = Vec::new();
+        let mut push = |key: &str, value: Option<String>| {
+            if let Some(value) = value {
+                fields.push(quote_field(&format!("{key}={value}")));
+            }
+        };
+
+        match self {
+            Self::Bind {
+                target,
+                source,
+                from,
+                readonly,
+            } => {
+                push("type", Some(String::from("bind")));
+                push("target", Some(target.clone()));
+                push("source", source.clone());
+                push("from", from.clone());
+                if !readonly {
+                    fields.push(String::from("rw"));
+                }
+            }
+            Self::Cache {
+                target,
+                id,
+                sharing,
+                source,
+                from,
+                readonly,
+                mode,
+                uid,
+                gid,
+            } => {
+                push("type", Some(String::from("cache")));
+                push("target", Some(target.clone()));
+                push("id", id.clone());
+                push("sharing", sharing.map(|s| s.to_string()));
+                push("source", source.clone());
+                push("from", from.clone());
+                push("mode", mode.map(|m| format!("{m:04o}")));
+                push("uid", uid.map(|u| u.to_string()));
+                push("gid", gid.map(|g| g.to_string()));
+                if *readonly {
+                    fields.push(String::from("readonly"));
+                }
+            }
+            Self::Tmpfs { target, size } => {
+                push("type", Some(String::from("tmpfs")));
+                push("target", Some(target.clone()));
+                push("size", size.map(format_size));
+            }
+            Self::Secret {
+                id,
+                target,
+                env,
+                required,
+                mode,
+                uid,
+                gid,
+            } => {
+                push("type", Some(String::from("secret")));
+                push("id", id.clone());
+                push("target", target.clone());
+                push("env", env.clone());
+                push("required", required.then(|| String::from("true")));
+                push("mode", mode.map(|m| format!("{m:04o}")));
+                push("uid", uid.map(|u| u.to_string()));
+                push("gid", gid.map(|g| g.to_string()));
+            }
+            Self::Ssh {
+                id,
+                target,
+                required,
+                mode,
+                uid,
+                gid,
+            } => {
+                push("type", Some(String::from("ssh")));
+                push("id", id.clone());
+                push("target", target.clone());
+                push("required", required.then(|| String::from("true")));
+                push("mode", mode.map(|m| format!("{m:04o}")));
+                push("uid", uid.map(|u| u.to_string()));
+                push("gid", gid.map(|g| g.to_string()));
+            }
+        }
+        write!(f, "{}", fields.join(","))
+    }
+}
+
+impl fmt::Display for Sharing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Shared => write!(f, "shared"),
+            Self::Private => write!(f, "private"),
+            Self::Locked => write!(f, "locked"),
+        }
+    }
+}
+
+/// Split the value of a `--mount` option into its fields, as a CSV record.
+///
+/// Like `BuildKit`, a field can be enclosed in double quotes to contain commas, e.g.
+/// `"target=/a,b"`, with `""` for a literal double quote. A double quote anywhere else is an error.
+fn split_fields(s: &str) -> Result<Vec<String>, ParseError> {
+    let error = |message: &str| {
+        ParseError::SyntaxError(Box::new(ErrorDetails {
+            found: Some(s.to_owned()),
+            ..ErrorDetails::new(format!("{message} in the mount option"))
+        }))
+    };
+
+    let mut fields = Vec::new();
+    let mut chars = s.chars().peekable();
+    loop {
+        let mut field = String::new();
+        if chars.next_if_eq(&DOUBLE_QUOTE).is_some() {
+            loop {
+                match chars.next() {
+                    Some(DOUBLE_QUOTE) if chars.next_if_eq(&DOUBLE_QUOTE).is_some() => {
+                        field.push(DOUBLE_QUOTE);
+                    }
+                    Some(DOUBLE_QUOTE) => break,
+                    Some(c) => field.push(c),
+                    None => return Err(error("unterminated quoted field")),
+                }
+            }
+            if chars.peek().is_some_and(|c| *c != COMMA) {
+                return Err(error("unexpected character after a quoted field"));
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| *c != COMMA) {
+                if c == DOUBLE_QUOTE {
+                    return Err(error(
+                        "a field must be quoted as a whole to contain a double quote",
+                    ));
+                }
+                field.push(c);
+            }
+        }
+        fields.push(field);
+
+        if chars.next().is_none() {
+            return Ok(fields);
+        }
+    }
+}
+
+/// Quote a field of a `--mount` option if it cannot be written as it is.
+fn quote_field(field: &str) -> String {
+    if field.contains([COMMA, DOUBLE_QUOTE]) {
+        let escaped = field.replace(DOUBLE_QUOTE, "\"\"");
+        format!("{DOUBLE_QUOTE}{escaped}{DOUBLE_QUOTE}")
+    } else {
+        field.to_owned()
+    }
+}
+
+fn invalid(key: &str, value: &str) -> ParseError {
+    ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+        format!("a valid value for the {key} mount option"),
+        value,
+    )))
+}
+
+/// Parse a flag that is enabled by its key alone, e.g. `readonly` or `required=true`.
+fn parse_bool(key: &str, value: Option<&str>) -> Result<bool, ParseError> {
+    match value.map(str::to_lowercase).as_deref() {
+        None | Some("true" | "1") => Ok(true),
+        Some("false" | "0") => Ok(false),
+        Some(value) => Err(invalid(key, value)),
+    }
+}
+
+const SIZE_UNITS: [(char, u64); 4] = [
+    ('t', 1 << 40),
+    ('g', 1 << 30),
+    ('m', 1 << 20),
+    ('k', 1 << 10),
+];
+
+/// Parse a size in bytes with an optional binary unit, e.g. `64m` or `1GiB`.
+fn parse_size(value: &str) -> Option<u64> {
+    let lowercase = value.to_lowercase();
+    let number = lowercase
+        .strip_suffix("ib")
+        .or_else(|| lowercase.strip_suffix('b'))
+        .unwrap_or(&lowercase);
+
+    let Some(unit) = number.chars().last().filter(char::is_ascii_alphabetic) else {
+        return number.parse().ok();
+    };
+    let (_, multiplier) = SIZE_UNITS.iter().find(|(u, _)| *u == unit)?;
+    let number: u64 = number[..number.len() - 1].parse().ok()?;
+
+    number.checked_mul(*multiplier)
+}
+
+/// Format a size with the largest unit that divides it exactly.
+fn format_size(size: u64) -> String {
+    SIZE_UNITS
+        .iter()
+        .find(|(_, multiplier)| size != 0 && size.is_multiple_of(*multiplier))
+        .map_or_else(
+            || size.to_string(),
+            |(unit, multiplier)| format!("{}{unit}", size / multiplier),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bind_mount() {
+        let mount: Mount = "src=.,dst=/src,from=builder,rw".parse().unwrap();
+
+        assert_eq!(
+            mount,
+            Mount::Bind {
+                target: String::from("/src"),
+                source: Some(String::from(".")),
+                from: Some(String::from("builder")),
+                readonly: false,
+            }
+        );
+        assert_eq!(
+            mount.to_string(),
+            "type=bind,target=/src,source=.,from=builder,rw"
+        );
+    }
+
+    #[test]
+    fn test_parse_cache_mount() {
+        let mount: Mount = "type=cache,id=cargo,target=/usr/local/cargo/registry,sharing=locked,mode=0755,uid=1001,ro"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            mount,
+            Mount::Cache {
+                target: String::from("/usr/local/cargo/registry"),
+                id: Some(String::from("cargo")),
+                sharing: Some(Sharing::Locked),
+                source: None,
+                from: None,
+                readonly: true,
+                mode: Some(0o755),
+                uid: Some(1001),
+                gid: None,
+            }
+        );
+        assert_eq!(
+            mount.to_string(),
+            "type=cache,target=/usr/local/cargo/registry,id=cargo,sharing=locked,mode=0755,uid=1001,readonly"
+        );
+    }
+
+    #[test]
+    fn test_parse_tmpfs_mount() {
+        let mount: Mount = "type=tmpfs,target=/tmp,size=64MiB".parse().unwrap();
+
+        assert_eq!(
+            mount,
+            Mount::Tmpfs {
+                target: String::from("/tmp"),
+                size: Some(64 << 20),
+            }
+        );
+        assert_eq!(mount.to_string(), "type=tmpfs,target=/tmp,size=64m");
+    }
+
+    #[test]
+    fn test_parse_secret_and_ssh_mounts() {
+        let secret: Mount = "type=secret,id=token,env=TOKEN,required".parse().unwrap();
+        let ssh: Mount = "type=ssh".parse().unwrap();
+
+        assert_eq!(
+            secret.to_string(),
+            "type=secret,id=token,env=TOKEN,required=true"
+        );
+        assert_eq!(ssh.to_string(), "type=ssh");
+    }
+
+    #[test]
+    fn test_parse_mount_with_unknown_key_fails() {
+        let result = "type=cache,target=/cache,color=blue".parse::<Mount>();
+
+        assert!(matches!(result, Err(ParseError::SyntaxError(_))));
+    }
+
+    #[test]
+    fn test_parse_mount_with_key_of_other_type_fails() {
+        let result = "type=tmpfs,target=/tmp,sharing=locked".parse::<Mount>();
+
+        assert!(matches!(result, Err(ParseError::SyntaxError(_))));
+    }
+
+    #[test]
+    fn test_parse_mount_with_quoted_fields() {
+        let mount: Mount = r#"type=bind,"target=/a,b","source=say ""hi""""#.parse().unwrap();
+
+        assert_eq!(
+            mount,
+            Mount::Bind {
+                target: String::from("/a,b"),
+                source: Some(String::from(r#"say "hi""#)),
+                from: None,
+                readonly: true,
+            }
+        );
+        assert_eq!(
+            mount.to_string(),
+            r#"type=bind,"target=/a,b","source=say ""hi""""#
+        );
+    }
+
+    #[test]
+    fn test_parse_mount_with_misplaced_quotes_fails() {
+        for value in [
+            r#"type=bind,target="/a,b""#,
+            r#"type=bind,"target=/a"b"#,
+            r#"type=bind,"target=/a"#,
+        ] {
+            let result = value.parse::<Mount>();
+            assert!(matches!(result, Err(ParseError::SyntaxError(_))), "{value}");
+        }
+    }
+
+    #[test]
+    fn test_parse_mount_without_target_fails() {
+        let result = "type=cache,id=cargo".parse::<Mount>();
+
+        assert!(matches!(result, Err(ParseError::MissingArgument(_))));
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("1k"), Some(1024));
+        assert_eq!(parse_size("2GB"), Some(2 << 30));
+        assert_eq!(parse_size("lots"), None);
+        assert_eq!(format_size(1536), "1536");
+    }
+}
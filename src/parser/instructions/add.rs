@@ -1,35 +1,70 @@
 use crate::ParseResult;
+use crate::ast::Heredoc;
 use crate::ast::Instruction;
+use crate::error::ErrorDetails;
 use crate::error::ParseError;
-use crate::parser::utils::get_options_from;
-use crate::quoter::Quoter;
+use crate::parser::utils::is_exec_form;
+use crate::parser::utils::parse_exec_form;
+use crate::parser::utils::parse_sources;
+use crate::parser::utils::split_options;
+use crate::parser::utils::split_values;
+use crate::word::Word;
 
-pub fn parse(arguments: &[String]) -> ParseResult<Instruction> {
-    let (options, remaining) = get_options_from(arguments);
+// https://docs.docker.com/reference/dockerfile/#add
+const FLAGS: [&str; 7] = [
+    "checksum",
+    "chown",
+    "chmod",
+    "exclude",
+    "keep-git-dir",
+    "link",
+    "unpack",
+];
+
+pub fn parse(arguments: &str, heredocs: &[Heredoc], escape: char) -> ParseResult<Instruction> {
+    let (options, remaining) = split_options(arguments, escape)?;
+    options.check(&FLAGS)?;
+
+    // paths with whitespace can be written in JSON form
+    let remaining = if is_exec_form(remaining) {
+        parse_exec_form(remaining)?
+            .into_iter()
+            .map(Word::new)
+            .collect()
+    } else {
+        split_values(remaining, escape)?
+    };
 
     if remaining.len() < 2 {
-        return Err(ParseError::MissingArgument(String::from(
+        return Err(ParseError::MissingArgument(Box::new(ErrorDetails::new(
             "ADD requires at least two arguments",
-        )));
+        ))));
     }
 
     let checksum = options.get("checksum").cloned();
     let chown = options.get("chown").cloned();
     let chmod = options.get("chmod").cloned();
-    let mut link = options.get("link").cloned();
-
-    if link.is_some() && link.clone().unwrap().is_empty() {
-        link = Some(String::from("true"));
-    }
+    let exclude = options.get_all("exclude").cloned().collect();
+    let keep_git_dir = options.flag("keep-git-dir")?;
+    let link = options.flag("link")?;
+    // archives are unpacked by default, unless they are downloaded
+    let unpack = options
+        .get("unpack")
+        .map(|_| options.flag("unpack"))
+        .transpose()?;
 
-    let mut sources: Vec<String> = remaining.iter().map(Quoter::dequote).collect();
-    let destination = sources.pop().unwrap().dequote();
+    let mut values = remaining;
+    let destination = values.pop().unwrap();
+    let sources = parse_sources(values, heredocs);
 
     Ok(Instruction::Add {
         checksum,
         chown,
         chmod,
+        exclude,
+        keep_git_dir,
         link,
+        unpack,
         sources,
         destination,
     })
@@ -38,29 +73,34 @@ pub fn parse(arguments: &[String]) -> ParseResult<Instruction> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ast::Source;
+    use crate::symbols::chars::BACKSLASH;
 
     #[test]
     fn test_add() {
-        let arguments = vec![
-            String::from("--checksum=sha256:abc123"),
-            String::from("--chown=root"),
-            String::from("--chmod=755"),
-            String::from("--link=false"),
-            String::from("file.txt"),
-            String::from("/tmp/file.txt"),
-        ];
-        let result = parse(&arguments).unwrap();
+        let arguments = "--checksum=sha256:abc123 --chown=root --chmod=755 --link=false --exclude=*.md --exclude=.git --keep-git-dir --unpack=false file.txt /tmp/file.txt";
+        let result = parse(arguments, &[], BACKSLASH).unwrap();
 
         assert_eq!(
             result,
             Instruction::Add {
-                checksum: Some(String::from("sha256:abc123")),
-                chown: Some(String::from("root")),
-                chmod: Some(String::from("755")),
-                link: Some(String::from("false")),
-                sources: vec![String::from("file.txt")],
-                destination: String::from("/tmp/file.txt"),
+                checksum: Some(Word::from("sha256:abc123")),
+                chown: Some(Word::from("root")),
+                chmod: Some(Word::from("755")),
+                exclude: vec![Word::from("*.md"), Word::from(".git")],
+                keep_git_dir: true,
+                link: false,
+                unpack: Some(false),
+                sources: vec![Source::Path(Word::from("file.txt"))],
+                destination: Word::from("/tmp/file.txt"),
             }
         )
     }
+
+    #[test]
+    fn test_add_with_unknown_flag_fails() {
+        let result = parse("--parents src /app/", &[], BACKSLASH);
+
+        assert!(matches!(result, Err(ParseError::SyntaxError(_))));
+    }
 }
@@ -1,7 +1,28 @@
+use crate::ParseResult;
 use crate::ast::Instruction;
 use crate::parser::utils::process_optional_key_value_pairs;
 
-pub fn parse(arguments: &[String]) -> Instruction {
-    let pairs = process_optional_key_value_pairs(arguments);
-    Instruction::Arg(pairs)
+pub fn parse(arguments: &str, escape: char) -> ParseResult<Instruction> {
+    let pairs = process_optional_key_value_pairs(arguments, escape)?;
+    Ok(Instruction::Arg(pairs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbols::chars::BACKSLASH;
+    use crate::word::Word;
+
+    #[test]
+    fn test_arg_with_multi_word_default() {
+        let result = parse("MESSAGE=\"hello  world\" VERSION", BACKSLASH).unwrap();
+
+        assert_eq!(
+            result,
+            Instruction::Arg(vec![
+                (String::from("MESSAGE"), Some(Word::from("hello  world"))),
+                (String::from("VERSION"), None),
+            ])
+        );
+    }
 }
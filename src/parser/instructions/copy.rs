@@ -1,35 +1,61 @@
 use crate::ParseResult;
+use crate::ast::Heredoc;
 use crate::ast::Instruction;
+use crate::error::ErrorDetails;
 use crate::error::ParseError;
-use crate::parser::utils::get_options_from;
-use crate::quoter::Quoter;
+use crate::image::parse_image_or_stage;
+use crate::parser::utils::is_exec_form;
+use crate::parser::utils::parse_exec_form;
+use crate::parser::utils::parse_sources;
+use crate::parser::utils::split_options;
+use crate::parser::utils::split_values;
+use crate::parser::utils::typed_text;
+use crate::word::Word;
 
-pub fn parse(arguments: &[String]) -> ParseResult<Instruction> {
-    let (options, remaining) = get_options_from(arguments);
+// https://docs.docker.com/reference/dockerfile/#copy
+const FLAGS: [&str; 6] = ["from", "chown", "chmod", "exclude", "link", "parents"];
+
+pub fn parse(arguments: &str, heredocs: &[Heredoc], escape: char) -> ParseResult<Instruction> {
+    let (options, remaining) = split_options(arguments, escape)?;
+    options.check(&FLAGS)?;
+
+    // paths with whitespace can be written in JSON form
+    let remaining = if is_exec_form(remaining) {
+        parse_exec_form(remaining)?
+            .into_iter()
+            .map(Word::new)
+            .collect()
+    } else {
+        split_values(remaining, escape)?
+    };
 
     if remaining.len() < 2 {
-        return Err(ParseError::MissingArgument(String::from(
+        return Err(ParseError::MissingArgument(Box::new(ErrorDetails::new(
             "COPY requires at least two arguments",
-        )));
+        ))));
     }
 
-    let from = options.get("from").cloned();
+    let from = options
+        .get("from")
+        .map(|from| parse_image_or_stage(&typed_text(from, escape)))
+        .transpose()?;
     let chown = options.get("chown").cloned();
     let chmod = options.get("chmod").cloned();
-    let mut link = options.get("link").cloned();
+    let exclude = options.get_all("exclude").cloned().collect();
+    let link = options.flag("link")?;
+    let parents = options.flag("parents")?;
 
-    if link.is_some() && link.clone().unwrap().is_empty() {
-        link = Some(String::from("true"));
-    }
-
-    let mut sources: Vec<String> = remaining.iter().map(Quoter::dequote).collect();
-    let destination = sources.pop().unwrap().dequote();
+    let mut values = remaining;
+    let destination = values.pop().unwrap();
+    let sources = parse_sources(values, heredocs);
 
     Ok(Instruction::Copy {
         from,
         chown,
         chmod,
+        exclude,
         link,
+        parents,
         sources,
         destination,
     })
@@ -38,29 +64,101 @@ pub fn parse(arguments: &[String]) -> ParseResult<Instruction> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ast::Source;
+    use crate::symbols::chars::BACKSLASH;
 
     #[test]
     fn test_copy() {
-        let arguments = vec![
-            String::from("--from=builder"),
-            String::from("--chown=root"),
-            String::from("--chmod=755"),
-            String::from("--link=false"),
-            String::from("file.txt"),
-            String::from("/tmp/file.txt"),
-        ];
-        let result = parse(&arguments).unwrap();
+        let arguments = "--from=builder --chown=root --chmod=755 --link --parents --exclude=*.txt --exclude=!keep.txt file.txt /tmp/file.txt";
+        let result = parse(arguments, &[], BACKSLASH).unwrap();
+
+        assert_eq!(
+            result,
+            Instruction::Copy {
+                from: Some("builder".parse().unwrap()),
+                chown: Some(Word::from("root")),
+                chmod: Some(Word::from("755")),
+                exclude: vec![Word::from("*.txt"), Word::from("!keep.txt")],
+                link: true,
+                parents: true,
+                sources: vec![Source::Path(Word::from("file.txt"))],
+                destination: Word::from("/tmp/file.txt"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_copy_with_quoted_paths() {
+        let result = parse("\"my file.txt\" 'other file.txt' /app/", &[], BACKSLASH).unwrap();
 
         assert_eq!(
             result,
             Instruction::Copy {
-                from: Some(String::from("builder")),
-                chown: Some(String::from("root")),
-                chmod: Some(String::from("755")),
-                link: Some(String::from("false")),
-                sources: vec![String::from("file.txt")],
-                destination: String::from("/tmp/file.txt"),
+                from: None,
+                chown: None,
+                chmod: None,
+                exclude: Vec::new(),
+                link: false,
+                parents: false,
+                sources: vec![
+                    Source::Path(Word::from("my file.txt")),
+                    Source::Path(Word::from("other file.txt")),
+                ],
+                destination: Word::from("/app/"),
             }
         );
     }
+
+    #[test]
+    fn test_copy_in_json_form() {
+        let result = parse("[\"my file.txt\", \"/app/\"]", &[], BACKSLASH).unwrap();
+
+        assert_eq!(
+            result,
+            Instruction::Copy {
+                from: None,
+                chown: None,
+                chmod: None,
+                exclude: Vec::new(),
+                link: false,
+                parents: false,
+                sources: vec![Source::Path(Word::from("my file.txt"))],
+                destination: Word::from("/app/"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_copy_with_heredoc() {
+        let heredocs = vec![Heredoc {
+            delimiter: String::from("EOF"),
+            strip_tabs: true,
+            quote: None,
+            body: String::from("\tport = 8080\n"),
+        }];
+        let result = parse("<<-EOF /etc/app.conf", &heredocs, BACKSLASH).unwrap();
+
+        assert_eq!(
+            result,
+            Instruction::Copy {
+                from: None,
+                chown: None,
+                chmod: None,
+                exclude: Vec::new(),
+                link: false,
+                parents: false,
+                sources: vec![Source::Heredoc(heredocs[0].clone())],
+                destination: Word::from("/etc/app.conf"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_copy_with_unknown_flag_fails() {
+        let result = parse("--keep-git-dir src /app/", &[], BACKSLASH);
+
+        assert!(result.is_err_and(|e| {
+            e.details().unwrap().found == Some(String::from("--keep-git-dir"))
+        }));
+    }
 }
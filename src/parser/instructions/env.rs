@@ -1,7 +1,8 @@
+use crate::ParseResult;
 use crate::ast::Instruction;
 use crate::parser::utils::process_key_value_pairs;
 
-pub fn parse(arguments: &[String]) -> Instruction {
-    let pairs = process_key_value_pairs(arguments);
-    Instruction::Env(pairs)
+pub fn parse(arguments: &str, escape: char) -> ParseResult<Instruction> {
+    let pairs = process_key_value_pairs(arguments, escape)?;
+    Ok(Instruction::Env(pairs))
 }
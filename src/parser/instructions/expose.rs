@@ -1,6 +1,44 @@
+use crate::ParseResult;
 use crate::ast::Instruction;
+use crate::parser::utils::split_values;
+use crate::parser::utils::typed_text;
+use crate::port::PortSpec;
 
-pub const fn parse(arguments: Vec<String>) -> Instruction {
-    let ports = arguments;
-    Instruction::Expose { ports }
+pub fn parse(arguments: &str, escape: char) -> ParseResult<Instruction> {
+    let ports = split_values(arguments, escape)?
+        .iter()
+        .map(|port| typed_text(port, escape).parse())
+        .collect::<ParseResult<Vec<PortSpec>>>()?;
+    Ok(Instruction::Expose { ports })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ParseError;
+    use crate::symbols::chars::BACKSLASH;
+
+    #[test]
+    fn test_expose_ports() {
+        let result = parse("80/tcp 53/udp 8000-8010 ${PORT}", BACKSLASH).unwrap();
+
+        assert_eq!(
+            result,
+            Instruction::Expose {
+                ports: vec![
+                    "80/tcp".parse().unwrap(),
+                    "53/udp".parse().unwrap(),
+                    "8000-8010".parse().unwrap(),
+                    PortSpec::Variable(String::from("${PORT}")),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_expose_invalid_port() {
+        let result = parse("80 abc", BACKSLASH);
+
+        assert!(matches!(result, Err(ParseError::SyntaxError(_))));
+    }
 }
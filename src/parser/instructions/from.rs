@@ -1,54 +1,63 @@
 use crate::ParseResult;
 use crate::ast::Instruction;
+use crate::error::ErrorDetails;
 use crate::error::ParseError;
-use crate::parser::utils::get_options_from;
+use crate::image::parse_image_or_stage;
+use crate::parser::utils::split_options;
+use crate::parser::utils::split_values;
+use crate::parser::utils::typed_text;
 
-pub fn parse(arguments: &[String]) -> ParseResult<Instruction> {
-    let (options, remaining) = get_options_from(arguments);
+pub fn parse(arguments: &str, escape: char) -> ParseResult<Instruction> {
+    let (options, remaining) = split_options(arguments, escape)?;
+    let remaining = split_values(remaining, escape)?;
 
     if remaining.is_empty() {
-        return Err(ParseError::MissingArgument(String::from(
+        return Err(ParseError::MissingArgument(Box::new(ErrorDetails::new(
             "FROM requires either one argument, or three: FROM <source> [AS <name>]",
-        )));
+        ))));
     }
 
     let platform = options.get("platform").cloned();
-    let image = remaining.first().unwrap().to_owned();
+    let image = parse_image_or_stage(&typed_text(remaining.first().unwrap(), escape))?;
     // check if there is an alias
     let keyword = remaining.get(1);
     let alias = remaining.get(2);
 
     if keyword.is_some() && alias.is_none() {
-        return Err(ParseError::MissingArgument(String::from(
+        return Err(ParseError::MissingArgument(Box::new(ErrorDetails::new(
             "FROM requires either one argument, or three: FROM <source> [AS <name>]",
-        )));
+        ))));
+    }
+
+    if let Some(keyword) = keyword.filter(|keyword| !keyword.eq_ignore_ascii_case("AS")) {
+        return Err(ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+            "the AS keyword",
+            keyword.as_str(),
+        ))));
     }
 
     Ok(Instruction::From {
         platform,
         image,
-        alias: alias.map(String::from),
+        alias: alias.map(ToString::to_string),
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::symbols::chars::BACKSLASH;
 
     #[test]
     fn test_from_with_alias() {
-        let arguments = vec![
-            String::from("ubuntu:latest"),
-            String::from("AS"),
-            String::from("builder"),
-        ];
-        let result = parse(&arguments).unwrap();
+        let arguments = "ubuntu:latest AS builder";
+        let result = parse(arguments, BACKSLASH).unwrap();
 
         assert_eq!(
             result,
             Instruction::From {
                 platform: None,
-                image: String::from("ubuntu:latest"),
+                image: "ubuntu:latest".parse().unwrap(),
                 alias: Some(String::from("builder")),
             }
         );
@@ -56,24 +65,54 @@ mod tests {
 
     #[test]
     fn test_from_without_alias() {
-        let arguments = vec![String::from("ubuntu:latest")];
-        let result = parse(&arguments).unwrap();
+        let arguments = "ubuntu:latest";
+        let result = parse(arguments, BACKSLASH).unwrap();
 
         assert_eq!(
             result,
             Instruction::From {
                 platform: None,
-                image: String::from("ubuntu:latest"),
+                image: "ubuntu:latest".parse().unwrap(),
                 alias: None,
             }
         );
     }
 
+    #[test]
+    fn test_from_with_lowercase_alias_keyword() {
+        let arguments = "ubuntu:latest as builder";
+        let result = parse(arguments, BACKSLASH).unwrap();
+
+        assert_eq!(
+            result,
+            Instruction::From {
+                platform: None,
+                image: "ubuntu:latest".parse().unwrap(),
+                alias: Some(String::from("builder")),
+            }
+        );
+    }
+
+    #[test]
+    fn test_from_with_invalid_alias_keyword() {
+        let arguments = "ubuntu:latest TO builder";
+        let result = parse(arguments, BACKSLASH);
+
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_from_with_invalid_alias() {
-        let arguments = vec![String::from("ubuntu:latest"), String::from("AS")];
-        let result = parse(&arguments);
+        let arguments = "ubuntu:latest AS";
+        let result = parse(arguments, BACKSLASH);
 
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_from_with_invalid_image() {
+        let result = parse("ubuntu:latest:24.04", BACKSLASH);
+
+        assert!(matches!(result, Err(ParseError::SyntaxError(_))));
+    }
 }
@@ -0,0 +1,152 @@
+use crate::ParseResult;
+use crate::ast::Healthcheck;
+use crate::ast::Instruction;
+use crate::error::ErrorDetails;
+use crate::error::ParseError;
+use crate::parser::utils::parse_command;
+use crate::parser::utils::parse_duration;
+use crate::parser::utils::split_options;
+
+pub fn parse(arguments: &str, escape: char) -> ParseResult<Instruction> {
+    let (options, remaining) = split_options(arguments, escape)?;
+
+    let Some(keyword) = remaining.split_whitespace().next() else {
+        return Err(ParseError::MissingArgument(Box::new(ErrorDetails::new(
+            "HEALTHCHECK requires either NONE or CMD <command>",
+        ))));
+    };
+
+    let arguments = remaining[keyword.len()..].trim_start();
+
+    if keyword.eq_ignore_ascii_case("NONE") {
+        if !arguments.is_empty() || !options.is_empty() {
+            return Err(ParseError::WrongNumberOfArguments(Box::new(
+                ErrorDetails::new("HEALTHCHECK NONE takes no options or arguments"),
+            )));
+        }
+        return Ok(Instruction::Healthcheck(Healthcheck::None));
+    }
+
+    if !keyword.eq_ignore_ascii_case("CMD") {
+        return Err(ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+            "NONE or CMD",
+            keyword,
+        ))));
+    }
+
+    if arguments.is_empty() {
+        return Err(ParseError::MissingArgument(Box::new(ErrorDetails::new(
+            "HEALTHCHECK CMD requires at least one argument",
+        ))));
+    }
+
+    let duration = |key: &str| {
+        options
+            .get(key)
+            .map(|value| {
+                parse_duration(value).ok_or_else(|| {
+                    ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+                        format!("a duration for --{key}"),
+                        value.as_str(),
+                    )))
+                })
+            })
+            .transpose()
+    };
+
+    let interval = duration("interval")?;
+    let timeout = duration("timeout")?;
+    let start_period = duration("start-period")?;
+    let start_interval = duration("start-interval")?;
+    let retries = options
+        .get("retries")
+        .map(|value| {
+            value.parse::<u32>().map_err(|_| {
+                ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+                    "a non-negative integer for --retries",
+                    value.as_str(),
+                )))
+            })
+        })
+        .transpose()?;
+
+    let command = parse_command(arguments);
+
+    Ok(Instruction::Healthcheck(Healthcheck::Cmd {
+        interval,
+        timeout,
+        start_period,
+        start_interval,
+        retries,
+        command,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::ast::Command;
+    use crate::symbols::chars::BACKSLASH;
+
+    #[test]
+    fn test_healthcheck_none() {
+        let result = parse("NONE", BACKSLASH).unwrap();
+
+        assert_eq!(result, Instruction::Healthcheck(Healthcheck::None));
+    }
+
+    #[test]
+    fn test_healthcheck_none_with_arguments_fails() {
+        let result = parse("NONE extra", BACKSLASH);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_healthcheck_cmd() {
+        let arguments = "--interval=5m --timeout=3s --start-period=1m30s --start-interval=500ms --retries=3 CMD [\"curl\", \"-f\", \"http://localhost/\"]";
+        let result = parse(arguments, BACKSLASH).unwrap();
+
+        assert_eq!(
+            result,
+            Instruction::Healthcheck(Healthcheck::Cmd {
+                interval: Some(Duration::from_mins(5)),
+                timeout: Some(Duration::from_secs(3)),
+                start_period: Some(Duration::from_secs(90)),
+                start_interval: Some(Duration::from_millis(500)),
+                retries: Some(3),
+                command: Command::Exec(vec![
+                    String::from("curl"),
+                    String::from("-f"),
+                    String::from("http://localhost/"),
+                ]),
+            })
+        );
+    }
+
+    #[test]
+    fn test_healthcheck_invalid_duration_fails() {
+        let result = parse("--interval=often CMD true", BACKSLASH);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_healthcheck_overflowing_duration_fails() {
+        let result = parse(
+            "--interval=99999999999999999999999999999h CMD true",
+            BACKSLASH,
+        );
+
+        assert!(matches!(result, Err(ParseError::SyntaxError(_))));
+    }
+
+    #[test]
+    fn test_healthcheck_missing_command_fails() {
+        let result = parse("CMD", BACKSLASH);
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,32 @@
+use crate::ParseResult;
+use crate::ast::Instruction;
+use crate::error::ErrorDetails;
+use crate::error::ParseError;
+
+pub fn parse(arguments: &str) -> ParseResult<Instruction> {
+    if arguments.is_empty() {
+        return Err(ParseError::MissingArgument(Box::new(ErrorDetails::new(
+            "MAINTAINER requires an argument",
+        ))));
+    }
+
+    let name = arguments.trim().to_owned();
+    Ok(Instruction::Maintainer { name })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maintainer() {
+        let result = parse("John Doe <john.doe@example.com>").unwrap();
+
+        assert_eq!(
+            result,
+            Instruction::Maintainer {
+                name: String::from("John Doe <john.doe@example.com>"),
+            }
+        );
+    }
+}
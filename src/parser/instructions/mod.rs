@@ -6,7 +6,10 @@ pub mod entrypoint;
 pub mod env;
 pub mod expose;
 pub mod from;
+pub mod healthcheck;
 pub mod label;
+pub mod maintainer;
+pub mod onbuild;
 pub mod run;
 pub mod shell;
 pub mod stopsignal;
@@ -0,0 +1,76 @@
+use crate::ParseResult;
+use crate::ast::Heredoc;
+use crate::ast::Instruction;
+use crate::error::ErrorDetails;
+use crate::error::ParseError;
+use crate::parser::parse_instruction;
+
+// https://docs.docker.com/reference/dockerfile/#onbuild-limitations
+const FORBIDDEN_TRIGGERS: [&str; 3] = ["ONBUILD", "FROM", "MAINTAINER"];
+
+pub fn parse(arguments: &str, heredocs: &[Heredoc], escape: char) -> ParseResult<Instruction> {
+    let Some(trigger) = arguments.split_whitespace().next() else {
+        return Err(ParseError::MissingArgument(Box::new(ErrorDetails::new(
+            "ONBUILD requires a trigger instruction",
+        ))));
+    };
+
+    if FORBIDDEN_TRIGGERS
+        .iter()
+        .any(|forbidden| trigger.eq_ignore_ascii_case(forbidden))
+    {
+        return Err(ParseError::SyntaxError(Box::new(ErrorDetails {
+            found: Some(trigger.to_owned()),
+            ..ErrorDetails::new(format!(
+                "{} isn't allowed as an ONBUILD trigger",
+                trigger.to_uppercase()
+            ))
+        })));
+    }
+
+    let instruction = parse_instruction(arguments.trim(), heredocs, escape)?;
+    Ok(Instruction::Onbuild(Box::new(instruction)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Source;
+    use crate::symbols::chars::BACKSLASH;
+    use crate::word::Word;
+
+    #[test]
+    fn test_onbuild() {
+        let result = parse("COPY . /app", &[], BACKSLASH).unwrap();
+
+        assert_eq!(
+            result,
+            Instruction::Onbuild(Box::new(Instruction::Copy {
+                from: None,
+                chown: None,
+                chmod: None,
+                exclude: Vec::new(),
+                link: false,
+                parents: false,
+                sources: vec![Source::Path(Word::from("."))],
+                destination: Word::from("/app"),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_onbuild_forbidden_triggers_fail() {
+        for trigger in ["ONBUILD", "FROM", "MAINTAINER"] {
+            let result = parse(&format!("{trigger} argument"), &[], BACKSLASH);
+
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn test_onbuild_unknown_trigger_fails() {
+        let result = parse("MAKE love", &[], BACKSLASH);
+
+        assert!(matches!(result, Err(ParseError::UnknownInstruction(_))));
+    }
+}
@@ -1,89 +1,131 @@
 use crate::ParseResult;
+use crate::ast::Heredoc;
 use crate::ast::Instruction;
+use crate::error::ErrorDetails;
 use crate::error::ParseError;
-use crate::parser::utils::clean_exec_form;
-use crate::parser::utils::clean_shell_form;
-use crate::parser::utils::get_options_from;
-use crate::parser::utils::is_exec_form;
-use crate::symbols::strings::HEREDOC_START;
-use crate::utils::split_heredoc;
+use crate::mount::Mount;
+use crate::parser::utils::parse_command;
+use crate::parser::utils::split_options;
 
-pub fn parse(arguments: &[String]) -> ParseResult<Instruction> {
-    let (options, remaining) = get_options_from(arguments);
+pub fn parse(arguments: &str, heredocs: &[Heredoc], escape: char) -> ParseResult<Instruction> {
+    let (options, remaining) = split_options(arguments, escape)?;
 
     if remaining.is_empty() {
-        return Err(ParseError::MissingArgument(String::from(
+        return Err(ParseError::MissingArgument(Box::new(ErrorDetails::new(
             "RUN requires at least one argument",
-        )));
+        ))));
     }
 
-    let mount = options.get("mount").cloned();
-    let network = options.get("network").cloned();
-    let security = options.get("security").cloned();
-
-    if remaining.iter().any(|arg| arg == HEREDOC_START) {
-        let lines = split_heredoc(remaining);
-
-        let command = lines.first().unwrap().clone();
-        let heredoc = Some(
-            lines[1..]
-                .to_vec()
-                .iter()
-                .map(|v| v.join(" "))
-                .collect::<Vec<String>>(),
-        );
-
-        return Ok(Instruction::Run {
-            mount,
-            network,
-            security,
-            command,
-            heredoc,
-        });
-    }
-
-    let command = if is_exec_form(&remaining) {
-        clean_exec_form(&remaining)
-    } else {
-        clean_shell_form(&remaining)
-    };
-    let heredoc = None;
+    let mounts = options
+        .get_all("mount")
+        .map(|mount| mount.parse())
+        .collect::<ParseResult<Vec<Mount>>>()?;
+    let network = options.get("network").map(ToString::to_string);
+    let security = options.get("security").map(ToString::to_string);
+    // the command keeps the redirections to the here-documents as written
+    let command = parse_command(remaining);
 
     Ok(Instruction::Run {
-        mount,
+        mounts,
         network,
         security,
         command,
-        heredoc,
+        heredocs: heredocs.to_vec(),
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ast::Command;
+    use crate::symbols::chars::BACKSLASH;
+
+    #[test]
+    fn test_run_with_quoted_mount_fields() {
+        let arguments = r#"--mount='type=bind,"target=/a,b"' ls /a,b"#;
+        let result = parse(arguments, &[], BACKSLASH).unwrap();
+
+        let Instruction::Run { mounts, .. } = &result else {
+            panic!("expected a RUN instruction");
+        };
+        assert_eq!(
+            mounts[0],
+            Mount::Bind {
+                target: String::from("/a,b"),
+                source: None,
+                from: None,
+                readonly: true,
+            }
+        );
+        assert_eq!(result.to_string(), format!("RUN {arguments}"));
+    }
 
     #[test]
     fn test_run() {
-        let arguments = vec![
-            String::from("--mount=type=bind,source=/host/path,target=/container/path"),
-            String::from("--network=host"),
-            String::from("--security=seccomp"),
-            String::from("cat"),
-            String::from("/etc/os-release"),
-        ];
-        let result = parse(&arguments).unwrap();
+        let arguments = "--mount=type=bind,source=/host/path,target=/container/path --mount=type=cache,target=/var/cache/dnf --network=host --security=seccomp cat /etc/os-release";
+        let result = parse(arguments, &[], BACKSLASH).unwrap();
 
         assert_eq!(
             result,
             Instruction::Run {
-                mount: Some(String::from(
-                    "type=bind,source=/host/path,target=/container/path"
-                )),
+                mounts: vec![
+                    Mount::Bind {
+                        target: String::from("/container/path"),
+                        source: Some(String::from("/host/path")),
+                        from: None,
+                        readonly: true,
+                    },
+                    Mount::Cache {
+                        target: String::from("/var/cache/dnf"),
+                        id: None,
+                        sharing: None,
+                        source: None,
+                        from: None,
+                        readonly: false,
+                        mode: None,
+                        uid: None,
+                        gid: None,
+                    },
+                ],
                 network: Some(String::from("host")),
                 security: Some(String::from("seccomp")),
-                command: vec![String::from("cat"), String::from("/etc/os-release")],
-                heredoc: None,
+                command: Command::Shell(String::from("cat /etc/os-release")),
+                heredocs: Vec::new(),
             }
         );
     }
+
+    #[test]
+    fn test_run_with_heredoc() {
+        let heredocs = vec![Heredoc {
+            delimiter: String::from("EOF"),
+            strip_tabs: false,
+            quote: None,
+            body: String::from("[global]\n"),
+        }];
+        let result = parse(
+            "--network=none cat <<EOF > /etc/pip.conf",
+            &heredocs,
+            BACKSLASH,
+        )
+        .unwrap();
+
+        assert_eq!(
+            result,
+            Instruction::Run {
+                mounts: Vec::new(),
+                network: Some(String::from("none")),
+                security: None,
+                command: Command::Shell(String::from("cat <<EOF > /etc/pip.conf")),
+                heredocs,
+            }
+        );
+    }
+
+    #[test]
+    fn test_run_with_invalid_mount_fails() {
+        let result = parse("--mount=type=volume,target=/data true", &[], BACKSLASH);
+
+        assert!(matches!(result, Err(ParseError::SyntaxError(_))));
+    }
 }
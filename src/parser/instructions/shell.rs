@@ -1,16 +1,40 @@
 use crate::ParseResult;
 use crate::ast::Instruction;
+use crate::error::ErrorDetails;
 use crate::error::ParseError;
-use crate::parser::utils::clean_exec_form;
 use crate::parser::utils::is_exec_form;
+use crate::parser::utils::parse_exec_form;
 
-pub fn parse(arguments: &[String]) -> ParseResult<Instruction> {
+pub fn parse(arguments: &str) -> ParseResult<Instruction> {
     if !is_exec_form(arguments) {
-        return Err(ParseError::SyntaxError(String::from(
-            "SHELL requires the arguments to be in JSON form",
-        )));
+        return Err(ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+            "the arguments in JSON form",
+            arguments,
+        ))));
     }
 
-    let shell = clean_exec_form(arguments);
+    let shell = parse_exec_form(arguments)?;
     Ok(Instruction::Shell(shell))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell() {
+        let result = parse("[\"/bin/bash\", \"-c\"]").unwrap();
+
+        assert_eq!(
+            result,
+            Instruction::Shell(vec![String::from("/bin/bash"), String::from("-c")])
+        );
+    }
+
+    #[test]
+    fn test_shell_with_malformed_json_fails() {
+        let result = parse("['/bin/bash', '-c']");
+
+        assert!(matches!(result, Err(ParseError::SyntaxError(_))));
+    }
+}
@@ -1,14 +1,44 @@
 use crate::ParseResult;
 use crate::ast::Instruction;
+use crate::error::ErrorDetails;
 use crate::error::ParseError;
+use crate::parser::utils::split_values;
+use crate::parser::utils::typed_text;
+use crate::signal::Signal;
 
-pub fn parse(arguments: &[String]) -> ParseResult<Instruction> {
+pub fn parse(arguments: &str, escape: char) -> ParseResult<Instruction> {
+    let arguments = split_values(arguments, escape)?;
     if arguments.len() != 1 {
-        return Err(ParseError::WrongNumberOfArguments(String::from(
-            "STOPSIGNAL requires exactly one argument",
+        return Err(ParseError::WrongNumberOfArguments(Box::new(
+            ErrorDetails::new("STOPSIGNAL requires exactly one argument"),
         )));
     }
 
-    let signal = arguments.first().unwrap().to_owned();
+    let signal: Signal = typed_text(arguments.first().unwrap(), escape).parse()?;
     Ok(Instruction::Stopsignal { signal })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbols::chars::BACKSLASH;
+
+    #[test]
+    fn test_stopsignal_normalizes_name() {
+        let result = parse("sigquit", BACKSLASH).unwrap();
+
+        assert_eq!(
+            result,
+            Instruction::Stopsignal {
+                signal: Signal::Name(String::from("SIGQUIT")),
+            }
+        );
+    }
+
+    #[test]
+    fn test_stopsignal_invalid() {
+        let result = parse("SIGNONE", BACKSLASH);
+
+        assert!(matches!(result, Err(ParseError::SyntaxError(_))));
+    }
+}
@@ -1,22 +1,46 @@
 use crate::ParseResult;
 use crate::ast::Instruction;
+use crate::error::ErrorDetails;
 use crate::error::ParseError;
-use crate::quoter::Quoter;
-use crate::symbols::chars::COLON;
+use crate::identity::parse_user;
+use crate::parser::utils::split_values;
+use crate::parser::utils::typed_text;
 
-pub fn parse(arguments: &[String]) -> ParseResult<Instruction> {
+pub fn parse(arguments: &str, escape: char) -> ParseResult<Instruction> {
+    let mut arguments = split_values(arguments, escape)?;
     if arguments.len() != 1 {
-        return Err(ParseError::WrongNumberOfArguments(String::from(
-            "USER requires exactly one argument",
+        return Err(ParseError::WrongNumberOfArguments(Box::new(
+            ErrorDetails::new("USER requires exactly one argument"),
         )));
     }
 
-    let user = arguments.first().unwrap().dequote();
-    // check if there is a group
-    let (user, group) = match user.split_once(COLON) {
-        Some((user, group)) => (user.to_owned(), Some(group.to_owned())),
-        None => (user, None),
-    };
-
+    let (user, group) = parse_user(&typed_text(&arguments.pop().unwrap(), escape))?;
     Ok(Instruction::User { user, group })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::Identity;
+    use crate::symbols::chars::BACKSLASH;
+
+    #[test]
+    fn test_user_with_group() {
+        let result = parse("app:1001", BACKSLASH).unwrap();
+
+        assert_eq!(
+            result,
+            Instruction::User {
+                user: Identity::Name(String::from("app")),
+                group: Some(Identity::Id(1001)),
+            }
+        );
+    }
+
+    #[test]
+    fn test_user_with_empty_group() {
+        let result = parse("app:", BACKSLASH);
+
+        assert!(matches!(result, Err(ParseError::SyntaxError(_))));
+    }
+}
@@ -1,14 +1,19 @@
+use crate::ParseResult;
 use crate::ast::Instruction;
-use crate::parser::utils::clean_exec_form;
-use crate::parser::utils::clean_shell_form;
 use crate::parser::utils::is_exec_form;
+use crate::parser::utils::parse_exec_form;
+use crate::parser::utils::split_values;
+use crate::word::Word;
 
-pub fn parse(arguments: &[String]) -> Instruction {
-    let mounts = if is_exec_form(arguments) {
-        clean_exec_form(arguments)
-    } else {
-        clean_shell_form(arguments)
-    };
+pub fn parse(arguments: &str, escape: char) -> ParseResult<Instruction> {
+    if is_exec_form(arguments)
+        && let Ok(mounts) = parse_exec_form(arguments)
+    {
+        let mounts = mounts.into_iter().map(Word::new).collect();
+        return Ok(Instruction::Volume { mounts });
+    }
 
-    Instruction::Volume { mounts }
+    // invalid JSON falls back to the space-separated form
+    let mounts = split_values(arguments, escape)?;
+    Ok(Instruction::Volume { mounts })
 }
@@ -1,15 +1,49 @@
 use crate::ParseResult;
 use crate::ast::Instruction;
+use crate::error::ErrorDetails;
 use crate::error::ParseError;
-use crate::quoter::Quoter;
+use crate::parser::utils::split_values;
+use crate::parser::utils::typed_text;
 
-pub fn parse(arguments: &[String]) -> ParseResult<Instruction> {
+pub fn parse(arguments: &str, escape: char) -> ParseResult<Instruction> {
+    let mut arguments = split_values(arguments, escape)?;
     if arguments.len() != 1 {
-        return Err(ParseError::WrongNumberOfArguments(String::from(
-            "WORKDIR requires exactly one argument",
+        return Err(ParseError::WrongNumberOfArguments(Box::new(
+            ErrorDetails::new("WORKDIR requires exactly one argument"),
         )));
     }
 
-    let path = arguments.first().unwrap().dequote();
+    let path = typed_text(&arguments.pop().unwrap(), escape).parse()?;
     Ok(Instruction::Workdir { path })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbols::chars::BACKSLASH;
+    use crate::workdir::WorkdirPath;
+
+    #[test]
+    fn test_workdir_with_quoted_path() {
+        let result = parse("\"/my app\"", BACKSLASH).unwrap();
+
+        assert_eq!(
+            result,
+            Instruction::Workdir {
+                path: WorkdirPath::Absolute(String::from("/my app")),
+            }
+        );
+    }
+
+    #[test]
+    fn test_workdir_with_relative_path() {
+        let result = parse("src", BACKSLASH).unwrap();
+
+        assert_eq!(
+            result,
+            Instruction::Workdir {
+                path: WorkdirPath::Relative(String::from("src")),
+            }
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use crate::ParseResult;
+use crate::error::ErrorDetails;
+use crate::error::ParseError;
+use crate::symbols::chars::DOLLAR;
+use crate::symbols::chars::DOUBLE_QUOTE;
+use crate::symbols::chars::SINGLE_QUOTE;
+use crate::word::Word;
+
+/// This struct represents a word of the instruction arguments and its location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    /// The word with quotes and escapes removed.
+    pub word: Word,
+    /// The byte offset of the first character of the word in the arguments.
+    pub start: usize,
+    /// The byte offset right after the last character of the word in the arguments.
+    pub end: usize,
+}
+
+/// This struct splits instruction arguments into words, following the quoting rules of Docker.
+///
+/// Words are separated by whitespace outside of quotes. Single quotes keep everything literally,
+/// double quotes keep everything except escaped double quotes, dollar signs and escape characters,
+/// and the
+/// escape character outside of quotes keeps the next character literally.
+#[derive(Debug, Clone)]
+pub struct Lexer<'a> {
+    text: &'a str,
+    escape: char,
+    position: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub const fn new(text: &'a str, escape: char) -> Self {
+        Self {
+            text,
+            escape,
+            position: 0,
+        }
+    }
+
+    /// Return the text that has not been split into words yet, without leading whitespace.
+    pub fn rest(&self) -> &'a str {
+        self.text[self.position..].trim_start()
+    }
+
+    /// Return the byte offset of the rest of the text.
+    pub fn offset(&self) -> usize {
+        self.text.len() - self.rest().len()
+    }
+
+    fn unterminated(&self, start: usize, quote: char) -> ParseError {
+        ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+            format!("a closing {quote}"),
+            &self.text[start..],
+        )))
+    }
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = ParseResult<Token>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.offset();
+        if start == self.text.len() {
+            self.position = start;
+            return None;
+        }
+
+        let mut word = Word::default();
+        let mut chars = self.text[start..].char_indices();
+        let mut end = self.text.len();
+
+        while let Some((index, c)) = chars.next() {
+            match c {
+                c if c.is_whitespace() => {
+                    end = start + index;
+                    break;
+                }
+                SINGLE_QUOTE => loop {
+                    match chars.next() {
+                        Some((_, SINGLE_QUOTE)) => break,
+                        Some((_, c)) => word.push(c, true),
+                        None => {
+                            self.position = self.text.len();
+                            return Some(Err(self.unterminated(start + index, SINGLE_QUOTE)));
+                        }
+                    }
+                },
+                DOUBLE_QUOTE => loop {
+                    match chars.next() {
+                        Some((_, DOUBLE_QUOTE)) => break,
+                        Some((_, c)) if c == self.escape => match chars.next() {
+                            Some((_, c)) if [DOUBLE_QUOTE, DOLLAR, self.escape].contains(&c) => {
+                                word.push(c, true);
+                            }
+                            Some((_, c)) => {
+                                word.push(self.escape, true);
+                                word.push(c, false);
+                            }
+                            None => {}
+                        },
+                        Some((_, c)) => word.push(c, false),
+                        None => {
+                            self.position = self.text.len();
+                            return Some(Err(self.unterminated(start + index, DOUBLE_QUOTE)));
+                        }
+                    }
+                },
+                c if c == self.escape => {
+                    if let Some((_, c)) = chars.next() {
+                        word.push(c, true);
+                    }
+                }
+                c => word.push(c, false),
+            }
+        }
+
+        self.position = end;
+        Some(Ok(Token { word, start, end }))
+    }
+}
+
+/// Split the arguments into words.
+pub fn split_words(text: &str, escape: char) -> ParseResult<Vec<Token>> {
+    Lexer::new(text, escape).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbols::chars::BACKSLASH;
+    use crate::symbols::chars::BACKTICK;
+
+    fn values(text: &str, escape: char) -> Vec<String> {
+        split_words(text, escape)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.word.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_split_words() {
+        assert_eq!(
+            values("  COPY   file.txt\t/app/ ", BACKSLASH),
+            vec!["COPY", "file.txt", "/app/"]
+        );
+    }
+
+    #[test]
+    fn test_split_words_with_quotes() {
+        assert_eq!(
+            values(
+                r#""my file.txt" desc="two  spaces" 'single "quoted"' my\ file"#,
+                BACKSLASH
+            ),
+            vec![
+                "my file.txt",
+                "desc=two  spaces",
+                "single \"quoted\"",
+                "my file"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_words_with_escapes_in_double_quotes() {
+        assert_eq!(
+            values(r#""say \"hello\"" "C:\path\\" "\$HOME""#, BACKSLASH),
+            vec!["say \"hello\"", "C:\\path\\", "$HOME"]
+        );
+        assert_eq!(
+            values(r#""C:\path `"quoted`"""#, BACKTICK),
+            vec!["C:\\path \"quoted\""]
+        );
+    }
+
+    #[test]
+    fn test_split_words_removes_quotes_and_escapes_of_dollars() {
+        assert_eq!(
+            values(r"'$HOME' \$HOME $HOME a\\$b", BACKSLASH),
+            vec!["$HOME", "$HOME", "$HOME", "a\\$b"]
+        );
+    }
+
+    #[test]
+    fn test_split_words_keeps_literal_dollars() {
+        let words =
+            split_words(r#"'$HOME' \$HOME "$HOME" "\$HOME" $HOME'$HOME'"#, BACKSLASH).unwrap();
+
+        let variables: Vec<bool> = words.iter().map(|t| t.word.has_variables()).collect();
+        assert_eq!(variables, vec![false, false, true, false, true]);
+        assert!(words[4].word.is_literal(5));
+    }
+
+    #[test]
+    fn test_split_words_offsets() {
+        let text = "--from=builder \"a b\" /app";
+        let words = split_words(text, BACKSLASH).unwrap();
+
+        let raw: Vec<&str> = words.iter().map(|t| &text[t.start..t.end]).collect();
+        assert_eq!(raw, vec!["--from=builder", "\"a b\"", "/app"]);
+    }
+
+    #[test]
+    fn test_lexer_rest() {
+        let mut lexer = Lexer::new("--network=host  echo \"unbalanced", BACKSLASH);
+
+        assert_eq!(lexer.next().unwrap().unwrap().word, "--network=host");
+        assert_eq!(lexer.rest(), "echo \"unbalanced");
+        assert_eq!(lexer.offset(), 16);
+    }
+
+    #[test]
+    fn test_split_words_with_unterminated_quote_fails() {
+        let result = split_words("\"unterminated value", BACKSLASH);
+
+        assert!(matches!(result, Err(ParseError::SyntaxError(_))));
+    }
+}
@@ -1,2 +1,65 @@
 pub mod instructions;
-mod utils;
+mod lexer;
+pub mod utils;
+
+use crate::ParseResult;
+use crate::ast::Heredoc;
+use crate::ast::Instruction;
+use crate::error::ErrorDetails;
+use crate::error::ParseError;
+use crate::parser::instructions::add;
+use crate::parser::instructions::arg;
+use crate::parser::instructions::cmd;
+use crate::parser::instructions::copy;
+use crate::parser::instructions::entrypoint;
+use crate::parser::instructions::env;
+use crate::parser::instructions::expose;
+use crate::parser::instructions::from;
+use crate::parser::instructions::healthcheck;
+use crate::parser::instructions::label;
+use crate::parser::instructions::maintainer;
+use crate::parser::instructions::onbuild;
+use crate::parser::instructions::run;
+use crate::parser::instructions::shell;
+use crate::parser::instructions::stopsignal;
+use crate::parser::instructions::user;
+use crate::parser::instructions::volume;
+use crate::parser::instructions::workdir;
+use crate::utils::split_instruction_and_arguments;
+
+/// Parse a single logical line and the here-documents following it into the instruction it
+/// represents.
+pub fn parse_instruction(
+    line: &str,
+    heredocs: &[Heredoc],
+    escape: char,
+) -> ParseResult<Instruction> {
+    let (instruction, arguments) = split_instruction_and_arguments(line)?;
+    // keywords are not case-sensitive
+    let result = match instruction.to_uppercase().as_str() {
+        "ADD" => add::parse(&arguments, heredocs, escape),
+        "ARG" => arg::parse(&arguments, escape),
+        "CMD" => Ok(cmd::parse(&arguments)),
+        "COPY" => copy::parse(&arguments, heredocs, escape),
+        "ENTRYPOINT" => Ok(entrypoint::parse(&arguments)),
+        "ENV" => env::parse(&arguments, escape),
+        "EXPOSE" => expose::parse(&arguments, escape),
+        "LABEL" => label::parse(&arguments, escape),
+        "FROM" => from::parse(&arguments, escape),
+        "HEALTHCHECK" => healthcheck::parse(&arguments, escape),
+        "MAINTAINER" => maintainer::parse(&arguments),
+        "ONBUILD" => onbuild::parse(&arguments, heredocs, escape),
+        "RUN" => run::parse(&arguments, heredocs, escape),
+        "SHELL" => shell::parse(&arguments),
+        "STOPSIGNAL" => stopsignal::parse(&arguments, escape),
+        "USER" => user::parse(&arguments, escape),
+        "VOLUME" => volume::parse(&arguments, escape),
+        "WORKDIR" => workdir::parse(&arguments, escape),
+        _ => {
+            return Err(ParseError::UnknownInstruction(Box::new(
+                ErrorDetails::expected("a Dockerfile instruction", instruction),
+            )));
+        }
+    };
+    result.map_err(|e| e.with_instruction(&instruction))
+}
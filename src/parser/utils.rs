@@ -1,188 +1,322 @@
-use std::collections::BTreeMap;
-use std::collections::HashMap;
-
-use crate::quoter::Quoter;
-use crate::symbols::chars::COMMA;
-use crate::symbols::chars::DOUBLE_QUOTE;
+use std::time::Duration;
+
+use crate::ParseResult;
+use crate::ast::Command;
+use crate::ast::Heredoc;
+use crate::ast::Source;
+use crate::error::ErrorDetails;
+use crate::error::ParseError;
+use crate::parser::lexer::Lexer;
+use crate::parser::lexer::split_words;
+use crate::symbols::chars::DOLLAR;
 use crate::symbols::chars::EQUALS;
 use crate::symbols::chars::LEFT_BRACKET;
 use crate::symbols::chars::RIGHT_BRACKET;
-use crate::symbols::chars::SINGLE_QUOTE;
 use crate::symbols::chars::SPACE;
 use crate::symbols::strings::EMPTY;
+use crate::symbols::strings::HEREDOC_START;
 use crate::symbols::strings::HYPHEN_MINUS;
+use crate::word::Word;
 
-pub fn is_exec_form(arguments: &[String]) -> bool {
-    matches!(
-        (arguments.first(), arguments.last()),
-        (Some(first), Some(last)) if first.starts_with(LEFT_BRACKET) && last.ends_with(RIGHT_BRACKET)
-    )
+pub fn is_exec_form(arguments: &str) -> bool {
+    let arguments = arguments.trim();
+    arguments.starts_with(LEFT_BRACKET) && arguments.ends_with(RIGHT_BRACKET)
 }
 
-pub fn clean_shell_form(arguments: &[String]) -> Vec<String> {
-    arguments
-        .iter()
-        .map(Quoter::dequote)
-        .filter(|arg| !arg.is_empty())
-        .collect()
+pub fn parse_command(arguments: &str) -> Command {
+    if is_exec_form(arguments)
+        && let Ok(arguments) = parse_exec_form(arguments)
+    {
+        return Command::Exec(arguments);
+    }
+    // invalid JSON falls back to the shell form, the shell takes care of quotes and escapes
+    Command::Shell(arguments.trim().to_owned())
 }
 
-pub fn clean_exec_form(arguments: &[String]) -> Vec<String> {
-    let mut result = Vec::new();
-    let mut current = String::new();
+pub fn parse_exec_form(arguments: &str) -> ParseResult<Vec<String>> {
+    let json = arguments.trim();
+    serde_json::from_str(json).map_err(|e| {
+        let details = ErrorDetails::expected("a JSON array of strings", json);
+        ParseError::SyntaxError(Box::new(ErrorDetails {
+            message: format!("{}: {e}", details.message),
+            ..details
+        }))
+    })
+}
 
-    for arg in arguments {
-        let trimmed = arg
-            .trim_start_matches(LEFT_BRACKET)
-            .trim_end_matches(RIGHT_BRACKET);
+/// The options of an instruction in the order they were written.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Options(Vec<(String, Word)>);
 
-        if !trimmed.starts_with(DOUBLE_QUOTE) && !trimmed.starts_with(SINGLE_QUOTE) {
-            current.push(SPACE);
-        }
+impl Options {
+    /// Return the value of the option, the last one if it was repeated.
+    pub fn get(&self, key: &str) -> Option<&Word> {
+        self.0.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
 
-        current.push_str(trimmed);
+    /// Return the values of a repeatable option in the order they were written.
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a Word> {
+        self.0.iter().filter(move |(k, _)| k == key).map(|(_, v)| v)
+    }
 
-        if trimmed.ends_with(COMMA) {
-            result.push(current.dequote().trim_end_matches(COMMA).to_owned());
-            current.clear();
+    /// Return whether the boolean flag is set, either alone or as `--flag=true`.
+    pub fn flag(&self, key: &str) -> ParseResult<bool> {
+        match self.get(key).map(Word::as_str) {
+            None | Some("false") => Ok(false),
+            Some(EMPTY | "true") => Ok(true),
+            Some(value) => Err(ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+                format!("true or false for --{key}"),
+                value,
+            )))),
         }
     }
-    result.push(current.dequote());
-    result
-}
 
-pub fn get_options_from(arguments: &[String]) -> (HashMap<String, String>, Vec<String>) {
-    let mut options = HashMap::new();
-    let mut remaining = Vec::new();
+    /// Fail on the first option that isn't one of the known options.
+    pub fn check(&self, known: &[&str]) -> ParseResult<()> {
+        let Some((key, _)) = self
+            .0
+            .iter()
+            .find(|(key, _)| !known.contains(&key.as_str()))
+        else {
+            return Ok(());
+        };
 
-    // some options can be passed multiple times
-    let mut options_counter = 0;
+        let known: Vec<String> = known.iter().map(|k| format!("{HYPHEN_MINUS}{k}")).collect();
+        Err(ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+            format!("one of the flags {}", known.join(", ")),
+            format!("{HYPHEN_MINUS}{key}"),
+        ))))
+    }
 
-    for arg in arguments {
-        if let Some(stripped) = arg.strip_prefix(HYPHEN_MINUS) {
-            options_counter += 1;
+    pub const fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
 
-            if let Some((key, value)) = stripped.split_once(EQUALS) {
-                options.insert(key.to_owned(), value.to_owned());
-            } else {
-                // some options can have default values
-                options.insert(stripped.to_owned(), EMPTY.to_owned());
-            }
-        } else {
+/// Split the leading `--key=value` options from the arguments.
+///
+/// Return the options together with the rest of the arguments as written.
+pub fn split_options(arguments: &str, escape: char) -> ParseResult<(Options, &str)> {
+    let mut options = Vec::new();
+    let mut lexer = Lexer::new(arguments, escape);
+
+    while lexer.rest().starts_with(HYPHEN_MINUS) {
+        let Some(token) = lexer.next().transpose()? else {
             break;
+        };
+        let option = token.word;
+
+        if let Some((key, value)) = option.split_once(EQUALS) {
+            options.push((key[HYPHEN_MINUS.len()..].to_owned(), value));
+        } else {
+            // some options can have default values
+            options.push((option[HYPHEN_MINUS.len()..].to_owned(), Word::default()));
         }
     }
 
-    remaining.extend(arguments.iter().skip(options_counter).cloned());
+    Ok((Options(options), lexer.rest()))
+}
 
-    (options, remaining)
+/// Split the arguments into the values of the words.
+pub fn split_values(arguments: &str, escape: char) -> ParseResult<Vec<Word>> {
+    Ok(split_words(arguments, escape)?
+        .into_iter()
+        .map(|token| token.word)
+        .collect())
 }
 
-pub fn process_key_value_pairs(arguments: &[String]) -> BTreeMap<String, String> {
-    let mut result = BTreeMap::new();
-    let mut last_key: Option<String> = None;
+/// Return the text to parse a typed value from.
+///
+/// A word with dollar signs is kept as written, so that the `Variable` variants of the typed values
+/// still know which of them are literal.
+pub fn typed_text(word: &Word, escape: char) -> String {
+    if word.contains(DOLLAR) {
+        word.written(escape)
+    } else {
+        word.to_string()
+    }
+}
 
-    for arg in arguments {
-        let (key, value) = if let Some((key, value)) = arg.split_once(EQUALS) {
-            (key.to_owned(), value.to_owned())
-        } else {
-            // try to append the value to the last key
-            if last_key.is_none() {
-                continue;
+/// Turn the values into sources, taking the here-documents in order for the values that open one.
+pub fn parse_sources(values: Vec<Word>, heredocs: &[Heredoc]) -> Vec<Source> {
+    let mut heredocs = heredocs.iter();
+    values
+        .into_iter()
+        .map(|value| {
+            if value.starts_with(HEREDOC_START)
+                && let Some(heredoc) = heredocs.next()
+            {
+                Source::Heredoc(heredoc.clone())
+            } else {
+                Source::Path(value)
             }
+        })
+        .collect()
+}
 
-            let last_key = last_key.unwrap();
-            let last_value = &result[&last_key];
+pub fn process_key_value_pairs(arguments: &str, escape: char) -> ParseResult<Vec<(Word, Word)>> {
+    let tokens = split_words(arguments, escape)?;
+
+    // the legacy form defines a single pair separated by whitespace
+    if let Some(first) = tokens.first()
+        && !first.word.contains(EQUALS)
+    {
+        if tokens.len() < 2 {
+            return Err(ParseError::MissingArgument(Box::new(
+                ErrorDetails::expected("a value after the key", first.word.as_str()),
+            )));
+        }
+        let mut value = Word::default();
+        for (index, token) in tokens[1..].iter().enumerate() {
+            if index > 0 {
+                value.push(SPACE, false);
+            }
+            value.push_word(&token.word);
+        }
+        return Ok(vec![(first.word.clone(), value)]);
+    }
 
-            let new_value = format!("{last_value} {arg}");
+    tokens
+        .into_iter()
+        .map(|token| match token.word.split_once(EQUALS) {
+            Some((key, value)) if !key.is_empty() => Ok((key, value)),
+            _ => Err(ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+                "<key>=<value>",
+                &arguments[token.start..token.end],
+            )))),
+        })
+        .collect()
+}
 
-            (last_key, new_value)
-        };
+pub fn process_optional_key_value_pairs(
+    arguments: &str,
+    escape: char,
+) -> ParseResult<Vec<(String, Option<Word>)>> {
+    Ok(split_words(arguments, escape)?
+        .into_iter()
+        .map(|token| match token.word.split_once(EQUALS) {
+            Some((key, value)) => (key.to_string(), Some(value)),
+            None => (token.word.to_string(), None),
+        })
+        .collect())
+}
 
-        result.insert(
-            key.clone(),
-            value
-                .trim_start_matches(DOUBLE_QUOTE)
-                .trim_end_matches(DOUBLE_QUOTE)
-                .to_owned(),
-        );
-        last_key = Some(key);
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    // https://pkg.go.dev/time#ParseDuration
+    if value == "0" {
+        return Some(Duration::ZERO);
     }
 
-    result
-}
+    let mut total = Duration::ZERO;
+    let mut rest = value;
+
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let unit_end = rest[number_end..]
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .map_or(rest.len(), |i| number_end + i);
+
+        let nanos_per_unit: u128 = match &rest[number_end..unit_end] {
+            "ns" => 1,
+            "us" | "µs" => 1_000,
+            "ms" => 1_000_000,
+            "s" => 1_000_000_000,
+            "m" => 60_000_000_000,
+            "h" => 3_600_000_000_000,
+            _ => return None,
+        };
 
-pub fn process_optional_key_value_pairs(arguments: &[String]) -> BTreeMap<String, Option<String>> {
-    let mut result = BTreeMap::new();
-
-    for arg in arguments {
-        let (key, value) = match arg.split_once(EQUALS) {
-            Some((key, value)) => (
-                key.to_owned(),
-                Some(
-                    value
-                        .trim_start_matches(DOUBLE_QUOTE)
-                        .trim_end_matches(DOUBLE_QUOTE)
-                        .to_owned(),
-                ),
-            ),
-            // ignore multi-word default values
-            None => (arg.to_owned(), None),
+        let (whole, fraction) = rest[..number_end]
+            .split_once('.')
+            .unwrap_or((&rest[..number_end], EMPTY));
+        if whole.is_empty() && fraction.is_empty() {
+            return None;
+        }
+
+        let mut nanos = if whole.is_empty() {
+            0
+        } else {
+            whole.parse::<u128>().ok()?.checked_mul(nanos_per_unit)?
         };
-        result.insert(key, value);
+        if !fraction.is_empty() {
+            // like Go, the digits beyond the precision of a 64-bit integer are dropped
+            let (mut digits, mut scale) = (0u128, 1u128);
+            for digit in fraction.chars() {
+                let digit = digit.to_digit(10)?;
+                if digits <= i64::MAX as u128 / 10 {
+                    digits = digits * 10 + u128::from(digit);
+                    scale *= 10;
+                }
+            }
+            nanos = nanos.checked_add((digits * nanos_per_unit + scale / 2) / scale)?;
+        }
+
+        total = total.checked_add(Duration::from_nanos(u64::try_from(nanos).ok()?))?;
+        rest = &rest[unit_end..];
     }
 
-    result
+    if value.is_empty() { None } else { Some(total) }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::symbols::chars::BACKSLASH;
+    use crate::symbols::chars::SINGLE_QUOTE;
 
     #[test]
     fn test_is_exec_form() {
-        assert!(is_exec_form(&[String::from("[\"/usr/bin/executable\"]")]));
-        assert!(is_exec_form(&[String::from(
-            "[\"/usr/bin/executable\", \"arg1\"]"
-        )]));
-        assert!(is_exec_form(&[String::from(
+        assert!(is_exec_form("[\"/usr/bin/executable\"]"));
+        assert!(is_exec_form("[\"/usr/bin/executable\", \"arg1\"]"));
+        assert!(is_exec_form(
             "[\"/usr/bin/executable\", \"arg1\", \"arg2\"]"
-        )]));
+        ));
     }
 
     #[test]
     fn test_is_not_exec_form() {
-        assert!(!is_exec_form(&[String::from("/usr/bin/executable")]));
-        assert!(!is_exec_form(&[String::from("/usr/bin/executable arg1")]));
-        assert!(!is_exec_form(&[String::from(
-            "/usr/bin/executable arg1 arg2"
-        )]));
+        assert!(!is_exec_form("/usr/bin/executable"));
+        assert!(!is_exec_form("/usr/bin/executable arg1"));
+        assert!(!is_exec_form("/usr/bin/executable arg1 arg2"));
+    }
+
+    #[test]
+    fn test_split_values() {
+        let values = split_values("echo \"Hello, World!\"", BACKSLASH).unwrap();
+
+        assert_eq!(
+            values,
+            vec![String::from("echo"), String::from("Hello, World!")]
+        );
     }
 
     #[test]
-    fn test_clean_shell_form() {
-        let shell_form = String::from("echo \"Hello, World!\"");
-        let arguments: Vec<String> = shell_form.split_whitespace().map(String::from).collect();
-        let cleaned = clean_shell_form(&arguments);
+    fn test_parse_sources() {
+        let heredoc = Heredoc {
+            delimiter: String::from("EOF"),
+            strip_tabs: false,
+            quote: Some(SINGLE_QUOTE),
+            body: String::from("numpy\n"),
+        };
+        let values = split_values("<<'EOF' requirements.txt", BACKSLASH).unwrap();
 
         assert_eq!(
-            cleaned,
+            parse_sources(values, std::slice::from_ref(&heredoc)),
             vec![
-                String::from("echo"),
-                String::from("Hello,"),
-                String::from("World!")
+                Source::Heredoc(heredoc),
+                Source::Path(Word::from("requirements.txt"))
             ]
         );
     }
 
     #[test]
-    fn test_clean_exec_form() {
-        let exec_form = String::from("[\"/usr/bin/executable\", \"arg1\", \"arg2\"]");
-        let arguments: Vec<String> = exec_form.split_whitespace().map(String::from).collect();
-        let cleaned = clean_exec_form(&arguments);
+    fn test_parse_exec_form() {
+        let parsed = parse_exec_form("[\"/usr/bin/executable\", \"arg1\", \"arg2\"]").unwrap();
 
         assert_eq!(
-            cleaned,
+            parsed,
             vec![
                 String::from("/usr/bin/executable"),
                 String::from("arg1"),
@@ -192,149 +326,264 @@ mod tests {
     }
 
     #[test]
-    fn test_clean_exec_form_with_spaces() {
-        let exec_form = String::from("[\"/usr/bin/executable\", \"arg1 with spaces\", \"arg2\"]");
-        let arguments: Vec<String> = exec_form.split_whitespace().map(String::from).collect();
-        let cleaned = clean_exec_form(&arguments);
+    fn test_parse_exec_form_with_spaces() {
+        let parsed =
+            parse_exec_form("[\"/usr/bin/executable\", \"arg1  with spaces\", \"arg2\"]").unwrap();
 
         assert_eq!(
-            cleaned,
+            parsed,
             vec![
                 String::from("/usr/bin/executable"),
-                String::from("arg1 with spaces"),
+                String::from("arg1  with spaces"),
                 String::from("arg2"),
             ]
         );
     }
 
     #[test]
-    fn test_get_options_from_arguments() {
-        let arguments = vec![
-            String::from("--option1=value1"),
-            String::from("--option2=value2"),
-            String::from("arg1"),
-            String::from("arg2"),
-        ];
-        let (options, remaining) = get_options_from(&arguments);
+    fn test_parse_exec_form_with_json_escapes() {
+        let parsed = parse_exec_form(r#"["sh", "-c", "echo \"a, b\"", "caf\u00e9", ""]"#).unwrap();
 
         assert_eq!(
-            options.get("option1"),
-            Some(String::from("value1")).as_ref()
+            parsed,
+            vec![
+                String::from("sh"),
+                String::from("-c"),
+                String::from("echo \"a, b\""),
+                String::from("café"),
+                String::new(),
+            ]
         );
+    }
+
+    #[test]
+    fn test_parse_malformed_exec_form_fails() {
+        let result = parse_exec_form("['echo', 'hello']");
+
+        assert!(matches!(result, Err(ParseError::SyntaxError(_))));
+    }
+
+    #[test]
+    fn test_split_options() {
+        let (options, remaining) = split_options(
+            "--option1=value1 --option2=\"two words\" arg1  arg2",
+            BACKSLASH,
+        )
+        .unwrap();
+
+        assert_eq!(options.get("option1"), Some(Word::from("value1")).as_ref());
         assert_eq!(
             options.get("option2"),
-            Some(String::from("value2")).as_ref()
+            Some(Word::from("two words")).as_ref()
         );
-        assert_eq!(remaining, vec!["arg1", "arg2"]);
+        assert_eq!(remaining, "arg1  arg2");
     }
 
     #[test]
-    fn test_get_options_from_no_arguments() {
-        let arguments = vec![
-            String::from("--option1=value1"),
-            String::from("--option2=value2"),
-        ];
-        let (options, remaining) = get_options_from(&arguments);
+    fn test_split_options_repeated() {
+        let (options, _) = split_options(
+            "--mount=type=cache,target=/a --network=none --mount=type=tmpfs,target=/b make",
+            BACKSLASH,
+        )
+        .unwrap();
 
         assert_eq!(
-            options.get("option1"),
-            Some(String::from("value1")).as_ref()
-        );
-        assert_eq!(
-            options.get("option2"),
-            Some(String::from("value2")).as_ref()
+            options.get_all("mount").collect::<Vec<&Word>>(),
+            vec!["type=cache,target=/a", "type=tmpfs,target=/b"]
         );
+        assert_eq!(options.get("mount").unwrap(), "type=tmpfs,target=/b");
+        assert_eq!(options.get("network").unwrap(), "none");
+    }
+
+    #[test]
+    fn test_options_flag() {
+        let (options, _) =
+            split_options("--link --parents=false --unpack=maybe src dst", BACKSLASH).unwrap();
+
+        assert!(options.flag("link").unwrap());
+        assert!(!options.flag("parents").unwrap());
+        assert!(!options.flag("exclude").unwrap());
+        assert!(options.flag("unpack").is_err());
+    }
+
+    #[test]
+    fn test_options_check() {
+        let (options, _) = split_options("--from=builder --chwon=root src dst", BACKSLASH).unwrap();
+
+        assert!(options.check(&["from", "chown"]).is_err_and(|e| {
+            e.details().unwrap().message
+                == "expected one of the flags --from, --chown, found --chwon"
+        }));
+        assert!(options.check(&["from", "chwon"]).is_ok());
+    }
+
+    #[test]
+    fn test_split_options_no_arguments() {
+        let (options, remaining) =
+            split_options("--option1=value1 --option2=value2", BACKSLASH).unwrap();
+
+        assert_eq!(options.get("option2").unwrap(), "value2");
         assert!(remaining.is_empty());
     }
 
     #[test]
-    fn test_get_options_from_no_options() {
-        let arguments = vec![String::from("arg1"), String::from("arg2")];
-        let (options, remaining) = get_options_from(&arguments);
+    fn test_split_options_no_options() {
+        let (options, remaining) = split_options("arg1 arg2", BACKSLASH).unwrap();
 
         assert!(options.is_empty());
-        assert_eq!(remaining, vec!["arg1", "arg2"]);
+        assert_eq!(remaining, "arg1 arg2");
+    }
+
+    #[test]
+    fn test_split_options_no_equals() {
+        let (options, remaining) =
+            split_options("--option1 --option2 arg1 arg2", BACKSLASH).unwrap();
+
+        assert_eq!(options.get("option1"), Some(Word::from("")).as_ref());
+        assert_eq!(options.get("option2"), Some(Word::from("")).as_ref());
+        assert_eq!(remaining, "arg1 arg2");
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("0"), Some(Duration::ZERO));
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("1m30s"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("1.5h"), Some(Duration::from_mins(90)));
+        assert_eq!(parse_duration("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(
+            parse_duration("0.99999999999999999999999999999h"),
+            Some(Duration::from_hours(1))
+        );
     }
 
     #[test]
-    fn test_get_options_from_no_equals() {
-        let arguments = vec![
-            String::from("--option1"),
-            String::from("--option2"),
-            String::from("arg1"),
-            String::from("arg2"),
-        ];
-        let (options, remaining) = get_options_from(&arguments);
+    fn test_parse_invalid_duration() {
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("30"), None);
+        assert_eq!(parse_duration("-5s"), None);
+        assert_eq!(parse_duration("5 s"), None);
+        assert_eq!(parse_duration("1d"), None);
+        assert_eq!(parse_duration("99999999999999999999999999999h"), None);
+    }
 
-        assert_eq!(options.get("option1"), Some(String::from("")).as_ref());
-        assert_eq!(options.get("option2"), Some(String::from("")).as_ref());
-        assert_eq!(remaining, vec!["arg1", "arg2"]);
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            parse_command("echo  \"$HOME\""),
+            Command::Shell(String::from("echo  \"$HOME\""))
+        );
+        assert_eq!(
+            parse_command("[\"echo\", \"$HOME\"]"),
+            Command::Exec(vec![String::from("echo"), String::from("$HOME")])
+        );
+
+        // invalid JSON is run by the shell
+        assert_eq!(
+            parse_command("['echo', 'hello']"),
+            Command::Shell(String::from("['echo', 'hello']"))
+        );
     }
 
     #[test]
     fn test_process_key_value_pairs_without_spaces() {
-        let arguments = vec![
-            String::from("key1=\"value1\""),
-            String::from("key2=\"value2\""),
-        ];
-        let result = process_key_value_pairs(&arguments);
+        let result = process_key_value_pairs("key1=\"value1\" key2=\"value2\"", BACKSLASH).unwrap();
 
-        assert_eq!(result.get("key1"), Some(String::from("value1")).as_ref());
-        assert_eq!(result.get("key2"), Some(String::from("value2")).as_ref());
+        assert_eq!(
+            result,
+            vec![
+                (Word::from("key1"), Word::from("value1")),
+                (Word::from("key2"), Word::from("value2")),
+            ]
+        );
     }
 
     #[test]
     fn test_process_key_value_pairs_without_spaces_and_quotes() {
-        let arguments = vec![String::from("key1=value1"), String::from("key2=value2")];
-        let result = process_key_value_pairs(&arguments);
+        let result = process_key_value_pairs("key1=value1 key2=value2", BACKSLASH).unwrap();
 
-        assert_eq!(result.get("key1"), Some(String::from("value1")).as_ref());
-        assert_eq!(result.get("key2"), Some(String::from("value2")).as_ref());
+        assert_eq!(
+            result,
+            vec![
+                (Word::from("key1"), Word::from("value1")),
+                (Word::from("key2"), Word::from("value2")),
+            ]
+        );
     }
 
     #[test]
     fn test_process_key_value_pairs_with_spaces_and_quotes() {
-        let arguments = vec![
-            String::from("key1=\"value1"),
-            String::from("with"),
-            String::from("spaces\""),
-            String::from("key2=\"value2"),
-            String::from("with"),
-            String::from("spaces\""),
-        ];
-        let result = process_key_value_pairs(&arguments);
+        let result = process_key_value_pairs(
+            "key1=\"value1 with spaces\" key2='value2  with spaces' key3=value3\\ with\\ spaces",
+            BACKSLASH,
+        )
+        .unwrap();
+
+        assert_eq!(
+            result,
+            vec![
+                (Word::from("key1"), Word::from("value1 with spaces")),
+                (Word::from("key2"), Word::from("value2  with spaces")),
+                (Word::from("key3"), Word::from("value3 with spaces")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_process_key_value_pairs_keeps_order_and_duplicates() {
+        let result = process_key_value_pairs("B=2 A=$B B=3", BACKSLASH).unwrap();
 
         assert_eq!(
-            result.get("key1"),
-            Some(String::from("value1 with spaces")).as_ref()
+            result,
+            vec![
+                (Word::from("B"), Word::from("2")),
+                (Word::from("A"), Word::from("$B")),
+                (Word::from("B"), Word::from("3")),
+            ]
         );
+    }
+
+    #[test]
+    fn test_process_key_value_pairs_in_legacy_form() {
+        let result = process_key_value_pairs("GREETING hello  world", BACKSLASH).unwrap();
+
         assert_eq!(
-            result.get("key2"),
-            Some(String::from("value2 with spaces")).as_ref()
+            result,
+            vec![(Word::from("GREETING"), Word::from("hello world"))]
         );
     }
 
+    #[test]
+    fn test_process_key_value_pairs_without_equals_fails() {
+        let result = process_key_value_pairs("key1=value1 value2", BACKSLASH);
+
+        assert!(matches!(result, Err(ParseError::SyntaxError(_))));
+    }
+
     #[test]
     fn test_process_optional_key_value_pairs_without_quotes() {
-        let arguments = vec![String::from("key1=value1"), String::from("key2")];
-        let result = process_optional_key_value_pairs(&arguments);
+        let result = process_optional_key_value_pairs("key2 key1=value1", BACKSLASH).unwrap();
 
         assert_eq!(
-            result.get("key1"),
-            Some(Some(String::from("value1"))).as_ref()
+            result,
+            vec![
+                (String::from("key2"), None),
+                (String::from("key1"), Some(Word::from("value1"))),
+            ]
         );
-        assert_eq!(result.get("key2"), Some(None).as_ref());
     }
 
     #[test]
     fn test_process_optional_key_value_pairs_with_quotes() {
-        let arguments = vec![String::from("key1=\"value1\""), String::from("key2")];
-        let result = process_optional_key_value_pairs(&arguments);
+        let result =
+            process_optional_key_value_pairs("key1=\"multi word value\" key2", BACKSLASH).unwrap();
 
         assert_eq!(
-            result.get("key1"),
-            Some(Some(String::from("value1"))).as_ref()
+            result,
+            vec![
+                (String::from("key1"), Some(Word::from("multi word value"))),
+                (String::from("key2"), None),
+            ]
         );
-        assert_eq!(result.get("key2"), Some(None).as_ref());
     }
 }
@@ -0,0 +1,192 @@
+// https://github.com/containerd/platforms/blob/main/platforms.go
+
+use std::env::consts;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::error::ErrorDetails;
+use crate::error::ParseError;
+use crate::symbols::chars::SLASH;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// This struct represents the platform of an image, e.g. `linux/arm64` or `linux/arm/v7`.
+///
+/// The components are normalized when parsing, so `Linux/x86_64` and `linux/amd64` are equal.
+///
+/// ### Example
+///
+/// ```
+/// use dockerfile_parser_rs::Platform;
+///
+/// let platform: Platform = "linux/aarch64".parse().unwrap();
+/// assert_eq!(platform.os, "linux");
+/// assert_eq!(platform.architecture, "arm64");
+/// assert_eq!(platform.variant, None);
+///
+/// let platform: Platform = "linux/armhf".parse().unwrap();
+/// assert_eq!(platform.to_string(), "linux/arm/v7");
+/// ```
+pub struct Platform {
+    pub os: String,
+    pub architecture: String,
+    /// The variant of the CPU, e.g. `v7` for `arm`.
+    pub variant: Option<String>,
+}
+
+impl Platform {
+    /// Return the platform of the running host.
+    pub fn host() -> Self {
+        let os = normalize_os(consts::OS);
+        let (architecture, variant) = normalize_architecture(consts::ARCH, None);
+        Self {
+            os,
+            architecture,
+            variant,
+        }
+    }
+
+    /// Return the automatic platform arguments for the given prefix, e.g.
+    /// `TARGETPLATFORM`, `TARGETOS`, `TARGETARCH` and `TARGETVARIANT` for `TARGET`.
+    pub(crate) fn args(&self, prefix: &str) -> [(String, String); 4] {
+        [
+            (format!("{prefix}PLATFORM"), self.to_string()),
+            (format!("{prefix}OS"), self.os.clone()),
+            (format!("{prefix}ARCH"), self.architecture.clone()),
+            (
+                format!("{prefix}VARIANT"),
+                self.variant.clone().unwrap_or_default(),
+            ),
+        ]
+    }
+}
+
+fn normalize_os(os: &str) -> String {
+    match os.to_lowercase().as_str() {
+        "macos" => String::from("darwin"),
+        os => os.to_owned(),
+    }
+}
+
+fn normalize_architecture(architecture: &str, variant: Option<&str>) -> (String, Option<String>) {
+    let architecture = architecture.to_lowercase();
+    let variant = variant.map(str::to_lowercase);
+
+    match (architecture.as_str(), variant.as_deref()) {
+        ("i386", _) => (String::from("386"), None),
+        ("x86_64" | "x86-64" | "amd64", None | Some("v1")) => (String::from("amd64"), None),
+        ("x86_64" | "x86-64" | "amd64", _) => (String::from("amd64"), variant),
+        ("aarch64" | "arm64", None | Some("8" | "v8")) => (String::from("arm64"), None),
+        ("aarch64" | "arm64", _) => (String::from("arm64"), variant),
+        ("armhf", _) | ("arm", None | Some("7")) => (String::from("arm"), Some(String::from("v7"))),
+        ("armel", _) => (String::from("arm"), Some(String::from("v6"))),
+        ("arm", Some(variant @ ("5" | "6" | "8"))) => {
+            (String::from("arm"), Some(format!("v{variant}")))
+        }
+        _ => (architecture, variant),
+    }
+}
+
+impl FromStr for Platform {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let components: Vec<&str> = s.split(SLASH).collect();
+        let valid = |component: &str| {
+            !component.is_empty()
+                && component
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        };
+
+        let (os, architecture, variant) = match components.as_slice() {
+            [os, architecture] => (*os, *architecture, None),
+            [os, architecture, variant] => (*os, *architecture, Some(*variant)),
+            _ => ("", "", None),
+        };
+        if !valid(os) || !valid(architecture) || !variant.is_none_or(valid) {
+            return Err(ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+                "a platform in the form os/arch[/variant]",
+                s,
+            ))));
+        }
+
+        let (architecture, variant) = normalize_architecture(architecture, variant);
+        Ok(Self {
+            os: normalize_os(os),
+            architecture,
+            variant,
+        })
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.os, self.architecture)?;
+        if let Some(variant) = &self.variant {
+            write!(f, "/{variant}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_platform_normalization() {
+        let cases = [
+            ("linux/amd64", "linux/amd64"),
+            ("Linux/x86_64", "linux/amd64"),
+            ("linux/aarch64", "linux/arm64"),
+            ("linux/arm64/v8", "linux/arm64"),
+            ("linux/arm", "linux/arm/v7"),
+            ("linux/arm/6", "linux/arm/v6"),
+            ("linux/armel", "linux/arm/v6"),
+            ("linux/i386", "linux/386"),
+            ("macos/arm64", "darwin/arm64"),
+            ("windows/amd64", "windows/amd64"),
+            ("linux/riscv64", "linux/riscv64"),
+        ];
+
+        for (platform, expected) in cases {
+            let result: Platform = platform.parse().unwrap();
+            assert_eq!(result.to_string(), expected, "{platform}");
+        }
+    }
+
+    #[test]
+    fn test_platform_invalid() {
+        for platform in [
+            "linux",
+            "linux/",
+            "/amd64",
+            "linux/amd64/v2/x",
+            "linux/$ARCH",
+        ] {
+            let result = platform.parse::<Platform>();
+            assert!(
+                matches!(result, Err(ParseError::SyntaxError(_))),
+                "{platform}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_platform_args() {
+        let platform: Platform = "linux/arm/v7".parse().unwrap();
+
+        assert_eq!(
+            platform.args("TARGET"),
+            [
+                (String::from("TARGETPLATFORM"), String::from("linux/arm/v7")),
+                (String::from("TARGETOS"), String::from("linux")),
+                (String::from("TARGETARCH"), String::from("arm")),
+                (String::from("TARGETVARIANT"), String::from("v7")),
+            ]
+        );
+    }
+}
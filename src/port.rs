@@ -0,0 +1,229 @@
+// https://docs.docker.com/reference/dockerfile/#expose
+
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::error::ErrorDetails;
+use crate::error::ParseError;
+use crate::symbols::chars::DOLLAR;
+use crate::symbols::chars::SLASH;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// This enum represents a port or a range of ports exposed by the EXPOSE instruction.
+///
+/// ### Example
+///
+/// ```
+/// use dockerfile_parser_rs::PortSpec;
+/// use dockerfile_parser_rs::Protocol;
+///
+/// let port: PortSpec = "8000-8010/udp".parse().unwrap();
+///
+/// assert_eq!(port.ports(), Some(8000..=8010));
+/// assert_eq!(port.protocol(), Some(Protocol::Udp));
+/// assert_eq!(port.to_string(), "8000-8010/udp");
+///
+/// let port: PortSpec = "${PORT}".parse().unwrap();
+/// assert_eq!(port, PortSpec::Variable(String::from("${PORT}")));
+/// ```
+pub enum PortSpec {
+    /// A single port, or a range of ports if there is an end.
+    Port {
+        start: u16,
+        end: Option<u16>,
+        /// The protocol, if it was given explicitly.
+        protocol: Option<Protocol>,
+    },
+    /// A port with variables that are not expanded yet, as written.
+    Variable(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// This enum represents the protocol of an exposed port.
+pub enum Protocol {
+    Tcp,
+    Udp,
+    Sctp,
+}
+
+impl PortSpec {
+    /// Return the range of exposed ports, or `None` for a port with variables.
+    pub fn ports(&self) -> Option<RangeInclusive<u16>> {
+        match self {
+            Self::Port { start, end, .. } => Some(*start..=end.unwrap_or(*start)),
+            Self::Variable(_) => None,
+        }
+    }
+
+    /// Return the protocol of the ports, which is TCP if none was given, or `None` for a port
+    /// with variables.
+    pub fn protocol(&self) -> Option<Protocol> {
+        match self {
+            Self::Port { protocol, .. } => Some(protocol.unwrap_or(Protocol::Tcp)),
+            Self::Variable(_) => None,
+        }
+    }
+}
+
+impl FromStr for PortSpec {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.contains(DOLLAR) {
+            return Ok(Self::Variable(s.to_owned()));
+        }
+
+        let (ports, protocol) = match s.split_once(SLASH) {
+            Some((ports, protocol)) => (ports, Some(protocol.parse()?)),
+            None => (s, None),
+        };
+        let port = |value: &str| {
+            value.parse::<u16>().map_err(|_| {
+                ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+                    "a port number between 0 and 65535",
+                    value,
+                )))
+            })
+        };
+
+        let (start, end) = match ports.split_once('-') {
+            Some((start, end)) => (port(start)?, Some(port(end)?)),
+            None => (port(ports)?, None),
+        };
+        if end.is_some_and(|end| end < start) {
+            return Err(ParseError::SyntaxError(Box::new(ErrorDetails {
+                found: Some(ports.to_owned()),
+                ..ErrorDetails::new(format!(
+                    "invalid range of ports {ports}, the end is lower than the start"
+                ))
+            })));
+        }
+
+        Ok(Self::Port {
+            start,
+            end,
+            protocol,
+        })
+    }
+}
+
+impl FromStr for Protocol {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "tcp" => Ok(Self::Tcp),
+            "udp" => Ok(Self::Udp),
+            "sctp" => Ok(Self::Sctp),
+            _ => Err(ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+                "one of tcp, udp or sctp",
+                s,
+            )))),
+        }
+    }
+}
+
+impl fmt::Display for PortSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Port {
+                start,
+                end,
+                protocol,
+            } => {
+                write!(f, "{start}")?;
+                if let Some(end) = end {
+                    write!(f, "-{end}")?;
+                }
+                if let Some(protocol) = protocol {
+                    write!(f, "/{protocol}")?;
+                }
+                Ok(())
+            }
+            Self::Variable(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp => write!(f, "tcp"),
+            Self::Udp => write!(f, "udp"),
+            Self::Sctp => write!(f, "sctp"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_port_spec_single_port() {
+        let port: PortSpec = "80".parse().unwrap();
+
+        assert_eq!(
+            port,
+            PortSpec::Port {
+                start: 80,
+                end: None,
+                protocol: None,
+            }
+        );
+        assert_eq!(port.ports(), Some(80..=80));
+        assert_eq!(port.protocol(), Some(Protocol::Tcp));
+    }
+
+    #[test]
+    fn test_port_spec_with_protocol() {
+        let port: PortSpec = "53/UDP".parse().unwrap();
+
+        assert_eq!(port.protocol(), Some(Protocol::Udp));
+        assert_eq!(port.to_string(), "53/udp");
+    }
+
+    #[test]
+    fn test_port_spec_range() {
+        let port: PortSpec = "8000-8010".parse().unwrap();
+
+        assert_eq!(port.ports(), Some(8000..=8010));
+        assert_eq!(port.to_string(), "8000-8010");
+    }
+
+    #[test]
+    fn test_port_spec_variable() {
+        let port: PortSpec = "${PORT}/tcp".parse().unwrap();
+
+        assert_eq!(port, PortSpec::Variable(String::from("${PORT}/tcp")));
+        assert_eq!(port.ports(), None);
+    }
+
+    #[test]
+    fn test_port_spec_invalid() {
+        let cases = [
+            (
+                "abc",
+                "expected a port number between 0 and 65535, found abc",
+            ),
+            (
+                "70000",
+                "expected a port number between 0 and 65535, found 70000",
+            ),
+            ("80/http", "expected one of tcp, udp or sctp, found http"),
+            (
+                "8010-8000",
+                "invalid range of ports 8010-8000, the end is lower than the start",
+            ),
+        ];
+
+        for (port, message) in cases {
+            let error = port.parse::<PortSpec>().unwrap_err();
+            assert_eq!(error.details().unwrap().message, message);
+        }
+    }
+}
@@ -1,17 +1,91 @@
+use crate::symbols::chars::DOLLAR;
 use crate::symbols::chars::DOUBLE_QUOTE;
-use crate::symbols::strings::EMPTY;
+use crate::symbols::chars::SINGLE_QUOTE;
+use crate::word::Word;
 
 pub trait Quoter {
-    fn dequote(&self) -> String;
-    fn enquote(&self) -> String;
+    /// Return the value in double quotes, escaped so that it is read back the same way.
+    fn enquote(&self, escape: char) -> String;
+
+    /// Return the value as is, or in double quotes if it would not be read back the same way.
+    fn quote(&self, escape: char) -> String;
 }
 
-impl Quoter for String {
-    fn dequote(&self) -> String {
-        self.replace(DOUBLE_QUOTE, EMPTY)
+impl Quoter for Word {
+    fn enquote(&self, escape: char) -> String {
+        let mut result = String::with_capacity(self.len() + 2);
+        let mut chars = self.char_indices().peekable();
+
+        result.push(DOUBLE_QUOTE);
+        while let Some((index, c)) = chars.next() {
+            // escape characters that would otherwise escape the next one
+            let escaped = match c {
+                DOUBLE_QUOTE => true,
+                DOLLAR => self.is_literal(index),
+                c if c == escape => chars
+                    .peek()
+                    .is_none_or(|(_, next)| [DOUBLE_QUOTE, DOLLAR, escape].contains(next)),
+                _ => false,
+            };
+            if escaped {
+                result.push(escape);
+            }
+            result.push(c);
+        }
+        result.push(DOUBLE_QUOTE);
+        result
+    }
+
+    fn quote(&self, escape: char) -> String {
+        let special = self.char_indices().any(|(index, c)| match c {
+            DOUBLE_QUOTE | SINGLE_QUOTE => true,
+            DOLLAR => self.is_literal(index),
+            c => c == escape || c.is_whitespace(),
+        });
+
+        if special || self.is_empty() {
+            self.enquote(escape)
+        } else {
+            self.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbols::chars::BACKSLASH;
+    use crate::symbols::chars::BACKTICK;
+
+    #[test]
+    fn test_enquote() {
+        let value = Word::from("say \"hello\"");
+        assert_eq!(value.enquote(BACKSLASH), "\"say \\\"hello\\\"\"");
+        assert_eq!(value.enquote(BACKTICK), "\"say `\"hello`\"\"");
+    }
+
+    #[test]
+    fn test_enquote_escape_characters() {
+        let value = Word::from("C:\\path\\ \\$HOME \\");
+        assert_eq!(value.enquote(BACKSLASH), "\"C:\\path\\ \\\\$HOME \\\\\"");
+        assert_eq!(value.enquote(BACKTICK), "\"C:\\path\\ \\$HOME \\\"");
+    }
+
+    #[test]
+    fn test_enquote_literal_dollars() {
+        let mut value = Word::literal("C:\\$HOME ");
+        value.push_word(&Word::from("$USER"));
+        assert_eq!(value.enquote(BACKSLASH), "\"C:\\\\\\$HOME $USER\"");
+        assert_eq!(value.enquote(BACKTICK), "\"C:\\`$HOME $USER\"");
     }
 
-    fn enquote(&self) -> String {
-        format!("\"{self}\"")
+    #[test]
+    fn test_quote() {
+        assert_eq!(Word::from("/app/$NAME").quote(BACKSLASH), "/app/$NAME");
+        assert_eq!(Word::from("my file").quote(BACKSLASH), "\"my file\"");
+        assert_eq!(Word::from("C:\\app").quote(BACKSLASH), "\"C:\\app\"");
+        assert_eq!(Word::from("C:\\app").quote(BACKTICK), "C:\\app");
+        assert_eq!(Word::literal("$HOME").quote(BACKSLASH), "\"\\$HOME\"");
+        assert_eq!(Word::from("").quote(BACKSLASH), "\"\"");
     }
 }
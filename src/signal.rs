@@ -0,0 +1,201 @@
+// https://github.com/moby/sys/blob/main/signal/signal_linux.go
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::error::ErrorDetails;
+use crate::error::ParseError;
+use crate::symbols::chars::DOLLAR;
+
+const PREFIX: &str = "SIG";
+const RTMIN: u32 = 34;
+const RTMAX: u32 = 64;
+// the offsets of the real-time signals that have a name
+const RT_OFFSETS: std::ops::RangeInclusive<u32> = 1..=15;
+
+const SIGNALS: [(&str, u32); 34] = [
+    ("ABRT", 6),
+    ("ALRM", 14),
+    ("BUS", 7),
+    ("CHLD", 17),
+    ("CLD", 17),
+    ("CONT", 18),
+    ("FPE", 8),
+    ("HUP", 1),
+    ("ILL", 4),
+    ("INT", 2),
+    ("IO", 29),
+    ("IOT", 6),
+    ("KILL", 9),
+    ("PIPE", 13),
+    ("POLL", 29),
+    ("PROF", 27),
+    ("PWR", 30),
+    ("QUIT", 3),
+    ("SEGV", 11),
+    ("STKFLT", 16),
+    ("STOP", 19),
+    ("SYS", 31),
+    ("TERM", 15),
+    ("TRAP", 5),
+    ("TSTP", 20),
+    ("TTIN", 21),
+    ("TTOU", 22),
+    ("URG", 23),
+    ("USR1", 10),
+    ("USR2", 12),
+    ("VTALRM", 26),
+    ("WINCH", 28),
+    ("XCPU", 24),
+    ("XFSZ", 25),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// This enum represents the signal of the STOPSIGNAL instruction.
+///
+/// Signal names are normalized to uppercase with the `SIG` prefix, e.g. `term` becomes `SIGTERM`.
+///
+/// ### Example
+///
+/// ```
+/// use dockerfile_parser_rs::Signal;
+///
+/// let signal: Signal = "term".parse().unwrap();
+/// assert_eq!(signal, Signal::Name(String::from("SIGTERM")));
+/// assert_eq!(signal.number(), Some(15));
+///
+/// let signal: Signal = "SIGRTMIN+3".parse().unwrap();
+/// assert_eq!(signal.number(), Some(37));
+/// ```
+pub enum Signal {
+    /// A signal name, including the real-time signals like `SIGRTMIN+1` or `SIGRTMAX-1`.
+    Name(String),
+    Number(u32),
+    /// A signal with variables that are not expanded yet, as written.
+    Variable(String),
+}
+
+impl Signal {
+    /// Return the number of the signal on Linux, or `None` for a signal with variables.
+    pub fn number(&self) -> Option<u32> {
+        match self {
+            Self::Name(name) => number(name.strip_prefix(PREFIX).unwrap_or(name)),
+            Self::Number(number) => Some(*number),
+            Self::Variable(_) => None,
+        }
+    }
+}
+
+/// Return the number of the signal with the given name, without the `SIG` prefix.
+fn number(name: &str) -> Option<u32> {
+    let offset = |value: &str| {
+        value
+            .parse()
+            .ok()
+            .filter(|offset| RT_OFFSETS.contains(offset))
+    };
+
+    match name {
+        "RTMIN" => Some(RTMIN),
+        "RTMAX" => Some(RTMAX),
+        _ => {
+            if let Some(value) = name.strip_prefix("RTMIN+") {
+                return offset(value).map(|offset| RTMIN + offset);
+            }
+            if let Some(value) = name.strip_prefix("RTMAX-") {
+                return offset(value).map(|offset| RTMAX - offset);
+            }
+            SIGNALS
+                .iter()
+                .find(|(signal, _)| *signal == name)
+                .map(|(_, number)| *number)
+        }
+    }
+}
+
+impl FromStr for Signal {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.contains(DOLLAR) {
+            return Ok(Self::Variable(s.to_owned()));
+        }
+
+        let invalid = || {
+            ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+                format!("a signal name or a number between 1 and {RTMAX}"),
+                s,
+            )))
+        };
+
+        if let Ok(number) = s.parse::<u32>() {
+            return if (1..=RTMAX).contains(&number) {
+                Ok(Self::Number(number))
+            } else {
+                Err(invalid())
+            };
+        }
+
+        let name = s.to_uppercase();
+        let name = name.strip_prefix(PREFIX).unwrap_or(&name);
+        match number(name) {
+            Some(_) => Ok(Self::Name(format!("{PREFIX}{name}"))),
+            None => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name(value) | Self::Variable(value) => write!(f, "{value}"),
+            Self::Number(number) => write!(f, "{number}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signal_names() {
+        let cases = [
+            ("SIGTERM", "SIGTERM", 15),
+            ("term", "SIGTERM", 15),
+            ("SigKill", "SIGKILL", 9),
+            ("RTMIN", "SIGRTMIN", 34),
+            ("SIGRTMIN+15", "SIGRTMIN+15", 49),
+            ("sigrtmax-14", "SIGRTMAX-14", 50),
+        ];
+
+        for (signal, name, number) in cases {
+            let result: Signal = signal.parse().unwrap();
+            assert_eq!(result, Signal::Name(String::from(name)));
+            assert_eq!(result.number(), Some(number));
+        }
+    }
+
+    #[test]
+    fn test_signal_number_and_variable() {
+        assert_eq!("9".parse::<Signal>().unwrap(), Signal::Number(9));
+        assert_eq!(
+            "$SIGNAL".parse::<Signal>().unwrap(),
+            Signal::Variable(String::from("$SIGNAL"))
+        );
+    }
+
+    #[test]
+    fn test_signal_invalid() {
+        for signal in ["0", "65", "-1", "SIGFOO", "RTMIN+16", "RTMAX-0", ""] {
+            let result = signal.parse::<Signal>();
+            assert!(
+                matches!(result, Err(ParseError::SyntaxError(_))),
+                "{signal}"
+            );
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+/// This struct represents the location of an instruction in the original text.
+///
+/// A span covers all continuation lines and here-document bodies of the instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Span {
+    /// The first line of the instruction, counting from 1.
+    pub start_line: usize,
+    /// The last line of the instruction, counting from 1.
+    pub end_line: usize,
+    /// The byte offset of the first character of the instruction.
+    pub start: usize,
+    /// The byte offset right after the last character of the instruction.
+    pub end: usize,
+}
+
+impl Span {
+    /// Return the text covered by the span.
+    ///
+    /// Return `None` if the span does not fit into the given text.
+    pub fn slice<'a>(&self, text: &'a str) -> Option<&'a str> {
+        text.get(self.start..self.end)
+    }
+}
@@ -0,0 +1,316 @@
+// https://docs.docker.com/build/building/multi-stage/
+
+use std::ops::Range;
+
+use crate::ast::Instruction;
+use crate::image::ImageRef;
+
+/// This struct represents the base of a build stage, as declared by its FROM instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FromSpec<'a> {
+    pub platform: Option<&'a str>,
+    pub image: &'a ImageRef,
+}
+
+/// This struct represents a build stage of a Dockerfile, from its FROM instruction up to the next
+/// one.
+///
+/// ### Example
+///
+/// ```
+/// use std::str::FromStr;
+///
+/// use dockerfile_parser_rs::Dockerfile;
+///
+/// let content = "ARG TAG=latest\nFROM rust AS build\nRUN cargo build\nFROM scratch\nCOPY --from=build /app /app\n";
+/// let dockerfile = Dockerfile::from_str(content).unwrap();
+///
+/// let build = dockerfile.stage("build").unwrap();
+/// assert_eq!(build.index, 0);
+/// assert_eq!(build.from.image.to_string(), "rust");
+/// assert_eq!(build.layers(), 1);
+///
+/// let stages: Vec<Option<&str>> = dockerfile.stages().iter().map(|s| s.alias).collect();
+/// assert_eq!(stages, vec![Some("build"), None]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stage<'a> {
+    /// The position of the stage in the Dockerfile, counting from 0.
+    pub index: usize,
+    /// The name given with `AS`, if there is any.
+    pub alias: Option<&'a str>,
+    pub from: FromSpec<'a>,
+    /// The instructions of the stage, starting with its FROM instruction.
+    pub instructions: &'a [Instruction],
+}
+
+impl<'a> Stage<'a> {
+    /// Create the view of a stage, or `None` if the instructions do not start with FROM.
+    pub(crate) fn new(index: usize, instructions: &'a [Instruction]) -> Option<Self> {
+        let Some(Instruction::From {
+            platform,
+            image,
+            alias,
+        }) = instructions.first()
+        else {
+            return None;
+        };
+
+        Some(Self {
+            index,
+            alias: alias.as_deref(),
+            from: FromSpec {
+                platform: platform.as_deref(),
+                image,
+            },
+            instructions,
+        })
+    }
+
+    /// Return the number of instructions in the stage.
+    pub fn steps(&self) -> usize {
+        count_steps(self.instructions)
+    }
+
+    /// Return the number of layers in the stage.
+    pub fn layers(&self) -> usize {
+        count_layers(self.instructions)
+    }
+}
+
+/// This struct represents a mutable view of a build stage.
+///
+/// The changes are made directly to the instructions of the Dockerfile, so the stage boundaries
+/// stay in sync with them. The FROM instruction of the stage cannot be removed, and new
+/// instructions cannot be inserted before it.
+///
+/// ### Example
+///
+/// ```
+/// use std::str::FromStr;
+///
+/// use dockerfile_parser_rs::Dockerfile;
+/// use dockerfile_parser_rs::Instruction;
+///
+/// let mut dockerfile = Dockerfile::from_str("FROM rust AS build\nFROM scratch\n").unwrap();
+///
+/// let mut build = dockerfile.stage_mut("build").unwrap();
+/// build.push(Instruction::Workdir { path: "/src".parse().unwrap() });
+///
+/// assert_eq!(dockerfile.instructions[1].to_string(), "WORKDIR /src");
+/// assert_eq!(dockerfile.stage("1").unwrap().instructions.len(), 1);
+/// ```
+#[derive(Debug)]
+pub struct StageMut<'a> {
+    index: usize,
+    range: Range<usize>,
+    instructions: &'a mut Vec<Instruction>,
+}
+
+impl<'a> StageMut<'a> {
+    pub(crate) const fn new(
+        index: usize,
+        range: Range<usize>,
+        instructions: &'a mut Vec<Instruction>,
+    ) -> Self {
+        Self {
+            index,
+            range,
+            instructions,
+        }
+    }
+
+    /// Return the read-only view of the stage, or `None` if its FROM instruction was replaced.
+    pub fn as_stage(&self) -> Option<Stage<'_>> {
+        Stage::new(self.index, &self.instructions[self.range.clone()])
+    }
+
+    /// Return the instructions of the stage for in-place changes, starting with its FROM
+    /// instruction.
+    pub fn instructions_mut(&mut self) -> &mut [Instruction] {
+        &mut self.instructions[self.range.clone()]
+    }
+
+    /// Append an instruction to the end of the stage.
+    pub fn push(&mut self, instruction: Instruction) {
+        self.instructions.insert(self.range.end, instruction);
+        self.range.end += 1;
+    }
+
+    /// Insert an instruction at the given position in the stage, after its FROM instruction.
+    ///
+    /// ## Panics
+    ///
+    /// Panic if the position is 0 or beyond the end of the stage.
+    pub fn insert(&mut self, position: usize, instruction: Instruction) {
+        assert!(
+            (1..=self.range.len()).contains(&position),
+            "position {position} is outside of the stage"
+        );
+        self.instructions
+            .insert(self.range.start + position, instruction);
+        self.range.end += 1;
+    }
+
+    /// Remove and return the instruction at the given position in the stage.
+    ///
+    /// ## Panics
+    ///
+    /// Panic if the position is 0, which is the FROM instruction, or beyond the end of the stage.
+    pub fn remove(&mut self, position: usize) -> Instruction {
+        assert!(
+            (1..self.range.len()).contains(&position),
+            "position {position} is outside of the stage"
+        );
+        self.range.end -= 1;
+        self.instructions.remove(self.range.start + position)
+    }
+}
+
+/// Return the ranges of the instructions of each stage.
+pub fn stage_ranges(instructions: &[Instruction]) -> Vec<Range<usize>> {
+    let starts: Vec<usize> = instructions
+        .iter()
+        .enumerate()
+        .filter(|(_, instruction)| matches!(instruction, Instruction::From { .. }))
+        .map(|(index, _)| index)
+        .collect();
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(index, start)| *start..starts.get(index + 1).copied().unwrap_or(instructions.len()))
+        .collect()
+}
+
+/// Find the stage with the given name, or with the given index if the name is a number.
+pub fn find_stage(instructions: &[Instruction], name: &str) -> Option<(usize, Range<usize>)> {
+    let ranges = stage_ranges(instructions);
+    let index = ranges
+        .iter()
+        .position(|range| match &instructions[range.start] {
+            Instruction::From {
+                alias: Some(alias), ..
+            } => alias.eq_ignore_ascii_case(name),
+            _ => false,
+        })
+        .or_else(|| name.parse().ok().filter(|index| *index < ranges.len()))?;
+
+    Some((index, ranges[index].clone()))
+}
+
+pub fn count_steps(instructions: &[Instruction]) -> usize {
+    instructions
+        .iter()
+        .filter(|i| {
+            !matches!(
+                i,
+                Instruction::Empty {} | Instruction::Comment { .. } | Instruction::Invalid(_)
+            )
+        })
+        .count()
+}
+
+pub fn count_layers(instructions: &[Instruction]) -> usize {
+    instructions
+        .iter()
+        .filter(|i| {
+            matches!(
+                i,
+                Instruction::Add { .. } | Instruction::Copy { .. } | Instruction::Run { .. }
+            )
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::file::Dockerfile;
+    use crate::word::Word;
+
+    fn mock_dockerfile() -> Dockerfile {
+        let mut content = String::new();
+        content.push_str("# syntax=docker/dockerfile:1\n");
+        content.push_str("ARG RUST=1.85\n");
+        content.push_str("FROM --platform=$BUILDPLATFORM rust:${RUST} AS Build\n");
+        content.push_str("RUN cargo build\n");
+        content.push('\n');
+        content.push_str("FROM scratch\n");
+        content.push_str("COPY --from=build /app /app\n");
+
+        Dockerfile::from_str(&content).unwrap()
+    }
+
+    #[test]
+    fn test_stage_ranges() {
+        let dockerfile = mock_dockerfile();
+
+        assert_eq!(stage_ranges(&dockerfile.instructions), vec![1..4, 4..6]);
+    }
+
+    #[test]
+    fn test_stages() {
+        let dockerfile = mock_dockerfile();
+        let stages = dockerfile.stages();
+
+        assert_eq!(stages.len(), 2);
+        assert_eq!(stages[0].alias, Some("Build"));
+        assert_eq!(stages[0].from.platform, Some("$BUILDPLATFORM"));
+        assert_eq!(stages[0].steps(), 2);
+        assert_eq!(stages[1].from.image, &ImageRef::Scratch);
+        assert_eq!(stages[1].layers(), 1);
+    }
+
+    #[test]
+    fn test_stage_lookup() {
+        let dockerfile = mock_dockerfile();
+
+        assert_eq!(dockerfile.stage("build").unwrap().index, 0);
+        assert_eq!(dockerfile.stage("1").unwrap().alias, None);
+        assert!(dockerfile.stage("2").is_none());
+        assert!(dockerfile.stage("release").is_none());
+    }
+
+    #[test]
+    fn test_global_args() {
+        let dockerfile = mock_dockerfile();
+
+        assert_eq!(
+            dockerfile.global_args().collect::<Vec<_>>(),
+            vec![&(String::from("RUST"), Some(Word::from("1.85")))]
+        );
+    }
+
+    #[test]
+    fn test_stage_mut_stays_in_sync() {
+        let mut dockerfile = mock_dockerfile();
+
+        let mut build = dockerfile.stage_mut("build").unwrap();
+        build.insert(
+            1,
+            Instruction::Workdir {
+                path: "/src".parse().unwrap(),
+            },
+        );
+        let removed = build.remove(3);
+        assert_eq!(removed, Instruction::Empty {});
+        assert_eq!(build.as_stage().unwrap().steps(), 3);
+
+        let mut last = dockerfile.stage_mut("1").unwrap();
+        last.instructions_mut()[1] = Instruction::Workdir {
+            path: "/app".parse().unwrap(),
+        };
+
+        let lines: Vec<String> = dockerfile
+            .instructions
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(lines[2], "WORKDIR /src");
+        assert_eq!(lines[4], "FROM scratch");
+        assert_eq!(lines[5], "WORKDIR /app");
+    }
+}
@@ -0,0 +1,763 @@
+// https://docs.docker.com/reference/dockerfile/#environment-replacement
+
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::str::FromStr;
+
+use regex::NoExpand;
+use regex::Regex;
+
+use crate::ParseResult;
+use crate::ast::Instruction;
+use crate::ast::Source;
+use crate::error::ErrorDetails;
+use crate::error::ParseError;
+use crate::identity::Identity;
+use crate::identity::parse_user;
+use crate::image::ImageRef;
+use crate::image::parse_image_or_stage;
+use crate::parser::utils::typed_text;
+use crate::port::PortSpec;
+use crate::signal::Signal;
+use crate::symbols::chars::DOLLAR;
+use crate::symbols::chars::DOUBLE_QUOTE;
+use crate::symbols::chars::LEFT_BRACE;
+use crate::symbols::chars::LEFT_BRACKET;
+use crate::symbols::chars::RIGHT_BRACE;
+use crate::symbols::chars::RIGHT_BRACKET;
+use crate::symbols::chars::SINGLE_QUOTE;
+use crate::symbols::chars::SLASH;
+use crate::word::Word;
+use crate::workdir::WorkdirPath;
+
+/// Expand the variables in a single word as written in a Dockerfile, following the rules of Docker.
+///
+/// Besides `$VAR` and `${VAR}`, the modifiers `${VAR:-word}`, `${VAR:+word}`, `${VAR:?message}`
+/// (and their forms without a colon, which only check whether the variable is set), `${VAR#pattern}`,
+/// `${VAR##pattern}`, `${VAR%pattern}`, `${VAR%%pattern}`, `${VAR/pattern/replacement}` and
+/// `${VAR//pattern/replacement}` are supported. Unset variables expand to an empty string.
+///
+/// The quotes and escapes of the word are removed along the way: single quotes and the escape
+/// character keep a dollar sign literal, while variables are still expanded inside double quotes.
+///
+/// ## Example
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use dockerfile_parser_rs::substitute;
+///
+/// let variables = HashMap::from([(String::from("VERSION"), String::from("1.2.3-rc1"))]);
+///
+/// let word = substitute("app-${VERSION%-*}-${ARCH:-amd64}.tar.gz", &variables, '\\').unwrap();
+/// assert_eq!(word, "app-1.2.3-amd64.tar.gz");
+///
+/// let word = substitute("\\$VERSION '$VERSION' \"$VERSION\"", &variables, '\\').unwrap();
+/// assert_eq!(word, "$VERSION $VERSION 1.2.3-rc1");
+/// ```
+///
+/// ## Errors
+///
+/// Return an error for malformed substitutions, for unterminated quotes and for
+/// `${VAR:?message}` if the variable is unset or empty.
+pub fn substitute<S: BuildHasher>(
+    word: &str,
+    variables: &HashMap<String, String, S>,
+    escape: char,
+) -> ParseResult<String> {
+    expand_word(word, variables, escape, Mode::Written)
+}
+
+/// How the quotes and the escape character of a word are treated during the expansion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// The word is written as in a Dockerfile, its quotes and escapes are removed.
+    Written,
+    /// Like `Written`, but the quoted and escaped characters stay escaped, so that a pattern
+    /// matches them literally.
+    Pattern,
+}
+
+fn expand_word<S: BuildHasher>(
+    word: &str,
+    variables: &HashMap<String, String, S>,
+    escape: char,
+    mode: Mode,
+) -> ParseResult<String> {
+    let mut result = String::new();
+    let mut quote = None;
+    let mut position = 0;
+    let literal = |result: &mut String, c: char| {
+        if mode == Mode::Pattern {
+            result.push(escape);
+        }
+        result.push(c);
+    };
+
+    while let Some(c) = word[position..].chars().next() {
+        let rest = &word[position..];
+        position += c.len_utf8();
+
+        match (quote, c) {
+            (Some(SINGLE_QUOTE), SINGLE_QUOTE) | (Some(DOUBLE_QUOTE), DOUBLE_QUOTE) => {
+                quote = None;
+                continue;
+            }
+            (Some(SINGLE_QUOTE), c) => {
+                literal(&mut result, c);
+                continue;
+            }
+            (None, SINGLE_QUOTE | DOUBLE_QUOTE) => {
+                quote = Some(c);
+                continue;
+            }
+            (_, c) if c == escape => {
+                // inside double quotes, only quotes, dollar signs and escapes are escaped
+                match word[position..].chars().next() {
+                    Some(next)
+                        if quote.is_none() || [DOUBLE_QUOTE, DOLLAR, escape].contains(&next) =>
+                    {
+                        literal(&mut result, next);
+                        position += next.len_utf8();
+                    }
+                    Some(_) => literal(&mut result, c),
+                    None => {}
+                }
+                continue;
+            }
+            (Some(_), c) if c != DOLLAR => {
+                literal(&mut result, c);
+                continue;
+            }
+            _ => {}
+        }
+
+        if c != DOLLAR {
+            result.push(c);
+            continue;
+        }
+
+        if rest[1..].starts_with(LEFT_BRACE) {
+            let end = closing_brace(rest, escape)?;
+            result.push_str(&expand_braces(&rest[2..end], variables, escape, mode)?);
+            position += end;
+            continue;
+        }
+
+        let name = variable_name(&rest[1..]);
+        if name.is_empty() {
+            // a dollar sign not followed by a name is literal
+            result.push(DOLLAR);
+        } else {
+            result.push_str(variables.get(name).map_or("", String::as_str));
+        }
+        position += name.len();
+    }
+
+    if let Some(quote) = quote {
+        return Err(ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+            format!("a closing {quote}"),
+            word,
+        ))));
+    }
+    Ok(result)
+}
+
+/// Return the byte offset of the brace closing the substitution at the start of the text.
+fn closing_brace(text: &str, escape: char) -> ParseResult<usize> {
+    let mut depth = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            c if c == escape => {
+                chars.next();
+            }
+            DOLLAR if chars.peek().is_some_and(|(_, next)| *next == LEFT_BRACE) => {
+                chars.next();
+                depth += 1;
+            }
+            RIGHT_BRACE => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(index);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Err(ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+        format!("a closing {RIGHT_BRACE}"),
+        text,
+    ))))
+}
+
+fn variable_name(text: &str) -> &str {
+    let end = text
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .unwrap_or(text.len());
+    &text[..end]
+}
+
+/// Expand the expression between `${` and `}`.
+fn expand_braces<S: BuildHasher>(
+    expression: &str,
+    variables: &HashMap<String, String, S>,
+    escape: char,
+    mode: Mode,
+) -> ParseResult<String> {
+    let name = variable_name(expression);
+    if name.is_empty() {
+        return Err(ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+            "a variable name",
+            format!("${{{expression}}}"),
+        ))));
+    }
+
+    let value = variables.get(name);
+    let modifier = &expression[name.len()..];
+    let word = |text: &str| expand_word(text, variables, escape, mode);
+    // the quoted and escaped characters of a pattern are matched literally
+    let pattern = |text: &str| expand_word(text, variables, escape, Mode::Pattern);
+
+    // the forms with a colon treat an empty value like an unset variable
+    let colon = modifier.starts_with(':');
+    let modifier = modifier.strip_prefix(':').unwrap_or(modifier);
+    let set = if colon {
+        value.is_some_and(|v| !v.is_empty())
+    } else {
+        value.is_some()
+    };
+    let value = value.cloned().unwrap_or_default();
+
+    if modifier.is_empty() && !colon {
+        return Ok(value);
+    }
+
+    if let Some(default) = modifier.strip_prefix('-') {
+        return if set { Ok(value) } else { word(default) };
+    }
+    if let Some(alternative) = modifier.strip_prefix('+') {
+        return if set {
+            word(alternative)
+        } else {
+            Ok(String::new())
+        };
+    }
+    if let Some(message) = modifier.strip_prefix('?') {
+        if set {
+            return Ok(value);
+        }
+        let message = word(message)?;
+        let message = if message.is_empty() {
+            format!("{name} is not set")
+        } else {
+            format!("{name}: {message}")
+        };
+        return Err(ParseError::MissingArgument(Box::new(ErrorDetails {
+            found: Some(String::from(name)),
+            ..ErrorDetails::new(message)
+        })));
+    }
+    if colon {
+        return Err(bad_substitution(expression));
+    }
+
+    if let Some(prefix) = modifier.strip_prefix("##") {
+        return Ok(remove_prefix(&value, &pattern(prefix)?, escape, true));
+    }
+    if let Some(prefix) = modifier.strip_prefix('#') {
+        return Ok(remove_prefix(&value, &pattern(prefix)?, escape, false));
+    }
+    if let Some(suffix) = modifier.strip_prefix("%%") {
+        return Ok(remove_suffix(&value, &pattern(suffix)?, escape, true));
+    }
+    if let Some(suffix) = modifier.strip_prefix('%') {
+        return Ok(remove_suffix(&value, &pattern(suffix)?, escape, false));
+    }
+    if let Some(replacement) = modifier.strip_prefix(SLASH) {
+        let all = replacement.starts_with(SLASH);
+        let replacement = replacement.strip_prefix(SLASH).unwrap_or(replacement);
+        let (search, replacement) =
+            split_unescaped(replacement, SLASH, escape).unwrap_or((replacement, ""));
+        let search = Regex::new(&pattern_to_regex(&pattern(search)?, escape))
+            .map_err(|_| bad_substitution(expression))?;
+        let replacement = word(replacement)?;
+
+        return Ok(if all {
+            search
+                .replace_all(&value, NoExpand(&replacement))
+                .into_owned()
+        } else {
+            search.replace(&value, NoExpand(&replacement)).into_owned()
+        });
+    }
+
+    Err(bad_substitution(expression))
+}
+
+/// Split the text at the first occurrence of the separator not preceded by the escape character.
+fn split_unescaped(text: &str, separator: char, escape: char) -> Option<(&str, &str)> {
+    let mut chars = text.char_indices();
+    while let Some((index, c)) = chars.next() {
+        if c == escape {
+            chars.next();
+        } else if c == separator {
+            return Some((&text[..index], &text[index + c.len_utf8()..]));
+        }
+    }
+    None
+}
+
+fn bad_substitution(expression: &str) -> ParseError {
+    ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+        "a valid substitution",
+        format!("${{{expression}}}"),
+    )))
+}
+
+/// Translate a shell pattern into a regular expression.
+fn pattern_to_regex(pattern: &str, escape: char) -> String {
+    let mut result = String::new();
+    let mut chars = pattern.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => result.push_str(".*"),
+            '?' => result.push('.'),
+            LEFT_BRACKET => {
+                let class: String = chars.by_ref().take_while(|c| *c != RIGHT_BRACKET).collect();
+                let class = class
+                    .strip_prefix('!')
+                    .map_or_else(|| class.clone(), |c| format!("^{c}"));
+                result.push(LEFT_BRACKET);
+                result.push_str(&class.replace('\\', "\\\\").replace('[', "\\["));
+                result.push(RIGHT_BRACKET);
+            }
+            c if c == escape => {
+                if let Some(next) = chars.next() {
+                    result.push_str(&regex::escape(&next.to_string()));
+                }
+            }
+            c => result.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    result
+}
+
+/// Return a regular expression matching the whole text against the shell pattern.
+fn whole_match(pattern: &str, escape: char) -> Option<Regex> {
+    Regex::new(&format!("^(?s:{})$", pattern_to_regex(pattern, escape))).ok()
+}
+
+fn remove_prefix(value: &str, pattern: &str, escape: char, longest: bool) -> String {
+    let Some(regex) = whole_match(pattern, escape) else {
+        return value.to_owned();
+    };
+    let mut ends: Vec<usize> = (0..=value.len())
+        .filter(|i| value.is_char_boundary(*i))
+        .collect();
+    if longest {
+        ends.reverse();
+    }
+
+    ends.into_iter()
+        .find(|end| regex.is_match(&value[..*end]))
+        .map_or_else(|| value.to_owned(), |end| value[end..].to_owned())
+}
+
+fn remove_suffix(value: &str, pattern: &str, escape: char, longest: bool) -> String {
+    let Some(regex) = whole_match(pattern, escape) else {
+        return value.to_owned();
+    };
+    let mut starts: Vec<usize> = (0..=value.len())
+        .filter(|i| value.is_char_boundary(*i))
+        .collect();
+    if !longest {
+        starts.reverse();
+    }
+
+    starts
+        .into_iter()
+        .find(|start| regex.is_match(&value[*start..]))
+        .map_or_else(|| value.to_owned(), |start| value[..start].to_owned())
+}
+
+/// The variables and the escape character used to expand the arguments of an instruction.
+struct Expander<'a, S> {
+    variables: &'a HashMap<String, String, S>,
+    escape: char,
+}
+
+impl<S: BuildHasher> Expander<'_, S> {
+    /// Expand the text as written, the result is not expanded again.
+    fn text(&self, text: &str) -> ParseResult<Word> {
+        expand_word(text, self.variables, self.escape, Mode::Written).map(Word::literal)
+    }
+
+    fn word(&self, word: &Word) -> ParseResult<Word> {
+        self.text(&word.written(self.escape))
+    }
+
+    /// Expand the `Variable` variant of a typed value, which is kept as written.
+    fn typed<T: FromStr<Err = ParseError>>(&self, text: &str) -> ParseResult<T> {
+        typed_text(&self.text(text)?, self.escape).parse()
+    }
+
+    fn optional(&self, word: Option<&Word>) -> ParseResult<Option<Word>> {
+        word.map(|word| self.word(word)).transpose()
+    }
+
+    fn words(&self, words: &[Word]) -> ParseResult<Vec<Word>> {
+        words.iter().map(|word| self.word(word)).collect()
+    }
+
+    fn pairs(&self, pairs: &[(Word, Word)]) -> ParseResult<Vec<(Word, Word)>> {
+        pairs
+            .iter()
+            .map(|(key, value)| Ok((self.word(key)?, self.word(value)?)))
+            .collect()
+    }
+
+    fn sources(&self, sources: &[Source]) -> ParseResult<Vec<Source>> {
+        sources
+            .iter()
+            .map(|source| match source {
+                Source::Path(path) => Ok(Source::Path(self.word(path)?)),
+                Source::Heredoc(_) => Ok(source.clone()),
+            })
+            .collect()
+    }
+
+    fn ports(&self, ports: &[PortSpec]) -> ParseResult<Vec<PortSpec>> {
+        ports
+            .iter()
+            .map(|port| match port {
+                PortSpec::Variable(value) => self.typed(value),
+                PortSpec::Port { .. } => Ok(port.clone()),
+            })
+            .collect()
+    }
+
+    fn user(
+        &self,
+        user: &Identity,
+        group: Option<&Identity>,
+    ) -> ParseResult<(Identity, Option<Identity>)> {
+        let variable = |identity: &Identity| matches!(identity, Identity::Variable(_));
+        if !variable(user) && !group.is_some_and(variable) {
+            return Ok((user.clone(), group.cloned()));
+        }
+        // the expanded value might contain the separator of the group
+        let value = group.map_or_else(|| user.to_string(), |group| format!("{user}:{group}"));
+        parse_user(&typed_text(&self.text(&value)?, self.escape))
+    }
+
+    fn image(&self, image: &ImageRef) -> ParseResult<ImageRef> {
+        match image {
+            ImageRef::Variable(reference) => {
+                parse_image_or_stage(&typed_text(&self.text(reference)?, self.escape))
+            }
+            _ => Ok(image.clone()),
+        }
+    }
+}
+
+impl Instruction {
+    /// Expand the variables in the arguments of the instruction.
+    ///
+    /// Only the arguments of the instructions supporting environment replacement are expanded:
+    /// `ADD`, `ARG`, `COPY`, `ENV`, `EXPOSE`, `FROM`, `LABEL`, `STOPSIGNAL`, `USER`, `VOLUME`,
+    /// `WORKDIR` and the triggers of `ONBUILD`. The commands of `RUN`, `CMD` and `ENTRYPOINT` are
+    /// left to the shell, and the bodies of here-documents are kept as written.
+    ///
+    /// The dollar signs that were quoted or escaped in the Dockerfile stay literal, see `Word`.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use std::str::FromStr;
+    ///
+    /// use dockerfile_parser_rs::Dockerfile;
+    /// use dockerfile_parser_rs::Instruction;
+    ///
+    /// let dockerfile = Dockerfile::from_str("WORKDIR /home/${USER:-root}/app\n").unwrap();
+    /// let variables = HashMap::from([(String::from("USER"), String::from("dev"))]);
+    ///
+    /// let workdir = dockerfile.instructions[0].substitute(&variables, '\\').unwrap();
+    /// assert_eq!(workdir.to_string(), "WORKDIR /home/dev/app");
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// Return an error if a substitution fails or if an expanded image reference is invalid.
+    pub fn substitute<S: BuildHasher>(
+        &self,
+        variables: &HashMap<String, String, S>,
+        escape: char,
+    ) -> ParseResult<Self> {
+        let expander = Expander { variables, escape };
+
+        let instruction = match self {
+            Self::Add {
+                checksum,
+                chown,
+                chmod,
+                exclude,
+                keep_git_dir,
+                link,
+                unpack,
+                sources,
+                destination,
+            } => Self::Add {
+                checksum: expander.optional(checksum.as_ref())?,
+                chown: expander.optional(chown.as_ref())?,
+                chmod: expander.optional(chmod.as_ref())?,
+                exclude: expander.words(exclude)?,
+                keep_git_dir: *keep_git_dir,
+                link: *link,
+                unpack: *unpack,
+                sources: expander.sources(sources)?,
+                destination: expander.word(destination)?,
+            },
+            Self::Arg(args) => Self::Arg(
+                args.iter()
+                    .map(|(name, default)| Ok((name.clone(), expander.optional(default.as_ref())?)))
+                    .collect::<ParseResult<_>>()?,
+            ),
+            Self::Copy {
+                from,
+                chown,
+                chmod,
+                exclude,
+                link,
+                parents,
+                sources,
+                destination,
+            } => Self::Copy {
+                from: from.as_ref().map(|from| expander.image(from)).transpose()?,
+                chown: expander.optional(chown.as_ref())?,
+                chmod: expander.optional(chmod.as_ref())?,
+                exclude: expander.words(exclude)?,
+                link: *link,
+                parents: *parents,
+                sources: expander.sources(sources)?,
+                destination: expander.word(destination)?,
+            },
+            Self::Env(env) => Self::Env(expander.pairs(env)?),
+            Self::Expose { ports } => Self::Expose {
+                ports: expander.ports(ports)?,
+            },
+            Self::From {
+                platform,
+                image,
+                alias,
+            } => Self::From {
+                platform: expander.optional(platform.as_ref())?,
+                image: expander.image(image)?,
+                alias: alias.clone(),
+            },
+            Self::Label(labels) => Self::Label(expander.pairs(labels)?),
+            Self::Onbuild(trigger) => {
+                Self::Onbuild(Box::new(trigger.substitute(variables, escape)?))
+            }
+            Self::Stopsignal { signal } => Self::Stopsignal {
+                signal: match signal {
+                    Signal::Variable(value) => expander.typed(value)?,
+                    _ => signal.clone(),
+                },
+            },
+            Self::User { user, group } => {
+                let (user, group) = expander.user(user, group.as_ref())?;
+                Self::User { user, group }
+            }
+            Self::Volume { mounts } => Self::Volume {
+                mounts: expander.words(mounts)?,
+            },
+            Self::Workdir { path } => Self::Workdir {
+                path: match path {
+                    WorkdirPath::Variable(value) => expander.typed(value)?,
+                    _ => path.clone(),
+                },
+            },
+            _ => self.clone(),
+        };
+        Ok(instruction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_instruction;
+    use crate::symbols::chars::BACKSLASH;
+    use crate::symbols::chars::BACKTICK;
+
+    fn variables() -> HashMap<String, String> {
+        HashMap::from([
+            (String::from("NAME"), String::from("app")),
+            (String::from("EMPTY"), String::new()),
+            (String::from("FILE"), String::from("/src/lib/file.tar.gz")),
+        ])
+    }
+
+    fn expand(word: &str) -> String {
+        substitute(word, &variables(), BACKSLASH).unwrap()
+    }
+
+    #[test]
+    fn test_substitute_simple_variables() {
+        assert_eq!(expand("$NAME/${NAME}_1"), "app/app_1");
+        assert_eq!(expand("$UNSET-${UNSET}"), "-");
+        assert_eq!(expand("cost: 5$ or $"), "cost: 5$ or $");
+    }
+
+    #[test]
+    fn test_substitute_escaped_dollar() {
+        assert_eq!(expand("\\$NAME \\${NAME}"), "$NAME ${NAME}");
+        assert_eq!(expand("a\\\\$NAME"), "a\\app");
+        assert_eq!(
+            substitute("`$NAME", &variables(), BACKTICK).unwrap(),
+            "$NAME"
+        );
+    }
+
+    #[test]
+    fn test_substitute_quotes() {
+        assert_eq!(expand("'$NAME'"), "$NAME");
+        assert_eq!(expand("\"$NAME \\$NAME\""), "app $NAME");
+        assert_eq!(expand("\"C:\\path\"\\ 'a\\b'"), "C:\\path a\\b");
+        assert_eq!(expand("${UNSET-'$NAME'}"), "$NAME");
+        assert_eq!(expand("${FILE%'.gz'}"), "/src/lib/file.tar");
+        assert_eq!(expand("${FILE%\".*\"}"), "/src/lib/file.tar.gz");
+
+        let result = substitute("'$NAME", &variables(), BACKSLASH);
+        assert!(matches!(result, Err(ParseError::SyntaxError(_))));
+    }
+
+    #[test]
+    fn test_substitute_defaults_and_alternatives() {
+        assert_eq!(expand("${UNSET:-default}"), "default");
+        assert_eq!(expand("${EMPTY:-default}"), "default");
+        assert_eq!(expand("${EMPTY-default}"), "");
+        assert_eq!(expand("${UNSET:-${NAME}-1}"), "app-1");
+        assert_eq!(expand("${NAME:+set}"), "set");
+        assert_eq!(expand("${EMPTY:+set}"), "");
+        assert_eq!(expand("${EMPTY+set}"), "set");
+        assert_eq!(expand("${UNSET+set}"), "");
+    }
+
+    #[test]
+    fn test_substitute_required_variable() {
+        assert_eq!(expand("${NAME:?needed}"), "app");
+        assert_eq!(expand("${EMPTY?needed}"), "");
+
+        let result = substitute("${EMPTY:?must be set}", &variables(), BACKSLASH);
+        assert!(result.is_err_and(|e| e.details().unwrap().message == "EMPTY: must be set"));
+    }
+
+    #[test]
+    fn test_substitute_patterns() {
+        assert_eq!(expand("${FILE#*/}"), "src/lib/file.tar.gz");
+        assert_eq!(expand("${FILE##*/}"), "file.tar.gz");
+        assert_eq!(expand("${FILE%.*}"), "/src/lib/file.tar");
+        assert_eq!(expand("${FILE%%.*}"), "/src/lib/file");
+        assert_eq!(expand("${FILE%.[tz]ip}"), "/src/lib/file.tar.gz");
+        assert_eq!(expand("${FILE/lib/pkg}"), "/src/pkg/file.tar.gz");
+        assert_eq!(expand("${FILE//\\//_}"), "_src_lib_file.tar.gz");
+        assert_eq!(expand("${NAME/?/A}"), "App");
+    }
+
+    #[test]
+    fn test_substitute_malformed_fails() {
+        for word in ["${NAME", "${}", "${NAME:}", "${NAME!}"] {
+            let result = substitute(word, &variables(), BACKSLASH);
+            assert!(matches!(result, Err(ParseError::SyntaxError(_))), "{word}");
+        }
+    }
+
+    #[test]
+    fn test_substitute_instruction() {
+        let instruction = Instruction::Label(vec![(
+            Word::from("$NAME.version"),
+            Word::from("${VERSION:-dev}"),
+        )]);
+
+        assert_eq!(
+            instruction.substitute(&variables(), BACKSLASH).unwrap(),
+            Instruction::Label(vec![(Word::from("app.version"), Word::from("dev"))])
+        );
+    }
+
+    #[test]
+    fn test_substitute_instruction_plain_values() {
+        let instruction = Instruction::Label(vec![(Word::from("path"), Word::from("'C:\\$NAME'"))]);
+
+        assert_eq!(
+            instruction.substitute(&variables(), BACKSLASH).unwrap(),
+            Instruction::Label(vec![(Word::from("path"), Word::from("'C:\\app'"))])
+        );
+    }
+
+    #[test]
+    fn test_substitute_instruction_env_keys() {
+        let instruction =
+            Instruction::Env(vec![(Word::from("${NAME}_HOME"), Word::from("/$NAME"))]);
+
+        assert_eq!(
+            instruction.substitute(&variables(), BACKSLASH).unwrap(),
+            Instruction::Env(vec![(Word::from("app_HOME"), Word::from("/app"))])
+        );
+    }
+
+    #[test]
+    fn test_substitute_instruction_image() {
+        let instruction = Instruction::From {
+            platform: None,
+            image: ImageRef::Variable(String::from("docker.io/$NAME:${TAG-stable}")),
+            alias: None,
+        };
+
+        assert_eq!(
+            instruction.substitute(&variables(), BACKSLASH).unwrap(),
+            Instruction::From {
+                platform: None,
+                image: "docker.io/app:stable".parse().unwrap(),
+                alias: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_substitute_instruction_keeps_literal_dollars() {
+        let variables = HashMap::from([(String::from("HOME"), String::from("/root"))]);
+        let env = parse_instruction(r#"ENV A='$HOME' B=\$HOME C="$HOME"'$HOME'"#, &[], BACKSLASH)
+            .unwrap()
+            .substitute(&variables, BACKSLASH)
+            .unwrap();
+
+        assert_eq!(
+            env,
+            Instruction::Env(vec![
+                (Word::from("A"), Word::literal("$HOME")),
+                (Word::from("B"), Word::literal("$HOME")),
+                (Word::from("C"), Word::literal("/root$HOME")),
+            ])
+        );
+        // the expanded values are not expanded again
+        assert_eq!(env.substitute(&variables, BACKSLASH).unwrap(), env);
+
+        let workdir = parse_instruction("WORKDIR '$HOME'/app", &[], BACKSLASH)
+            .unwrap()
+            .substitute(&variables, BACKSLASH)
+            .unwrap();
+        assert_eq!(
+            workdir,
+            Instruction::Workdir {
+                path: WorkdirPath::Variable(String::from("\\$HOME/app")),
+            }
+        );
+    }
+}
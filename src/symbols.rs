@@ -1,20 +1,26 @@
 pub mod chars {
+    pub const AT: char = '@';
     pub const BACKSLASH: char = '\\';
+    pub const BACKTICK: char = '`';
     pub const COLON: char = ':';
     pub const COMMA: char = ',';
-    pub const SINGLE_QUOTE: char = '\'';
+    pub const DOLLAR: char = '$';
     pub const DOUBLE_QUOTE: char = '"';
     pub const EQUALS: char = '=';
     pub const HASHTAG: char = '#';
+    pub const LEFT_BRACE: char = '{';
     pub const LEFT_BRACKET: char = '[';
+    pub const LESS_THAN: char = '<';
+    pub const RIGHT_BRACE: char = '}';
     pub const RIGHT_BRACKET: char = ']';
+    pub const SINGLE_QUOTE: char = '\'';
+    pub const SLASH: char = '/';
     pub const SPACE: char = ' ';
+    pub const TAB: char = '\t';
 }
 
 pub mod strings {
     pub const EMPTY: &str = "";
-    pub const HEREDOC_END: &str = "EOF";
-    pub const HEREDOC_NEWLINE: &str = "--NEWLINE--";
-    pub const HEREDOC_START: &str = "<<EOF";
+    pub const HEREDOC_START: &str = "<<";
     pub const HYPHEN_MINUS: &str = "--";
 }
@@ -0,0 +1,250 @@
+use std::fmt;
+
+use crate::ast::Heredoc;
+use crate::directives::Directives;
+use crate::directives::parse_directives;
+use crate::error::ParseError;
+use crate::span::Span;
+use crate::symbols::chars::HASHTAG;
+use crate::utils::process_dockerfile_content;
+
+/// The kind of a node in the syntax tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    /// A parser directive at the top of the file.
+    Directive,
+    /// An empty line or a line with whitespace only.
+    Empty,
+    /// A comment line.
+    Comment,
+    /// An instruction, including its continuation lines and here-document bodies.
+    Instruction,
+}
+
+/// This struct represents a node of the syntax tree, covering one logical line of the original
+/// text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxNode {
+    pub kind: NodeKind,
+    /// The original text of the node, with whitespace, continuations and inline comments.
+    pub text: String,
+    /// The line break following the node, empty at the end of the text.
+    pub newline: String,
+    pub span: Span,
+    /// The logical line with continuations joined and inline comments removed.
+    content: String,
+    /// The here-documents following the logical line.
+    heredocs: Vec<Heredoc>,
+}
+
+impl SyntaxNode {
+    /// Return the logical line the typed instruction is parsed from.
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// Return the here-documents the typed instruction is parsed with.
+    pub fn heredocs(&self) -> &[Heredoc] {
+        &self.heredocs
+    }
+}
+
+/// This struct represents the lossless syntax tree of a Dockerfile.
+///
+/// The tree is flat: it is only a list of nodes, one for each logical line of the original text,
+/// holding the raw text of the line. The nodes have no token structure, so the words and options
+/// of an instruction are only found in the typed [`Instruction`](crate::Instruction). Writing the
+/// nodes one after another reproduces the original text byte for byte.
+///
+/// ## Example
+///
+/// ```
+/// use dockerfile_parser_rs::SyntaxTree;
+///
+/// let content = "FROM scratch\r\nRUN echo \\\n  hello # greeting\n";
+/// let tree = SyntaxTree::parse(content);
+///
+/// assert_eq!(tree.nodes().len(), 2);
+/// assert_eq!(tree.nodes()[1].content(), "RUN echo hello # greeting");
+/// assert_eq!(tree.to_string(), content);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyntaxTree {
+    nodes: Vec<SyntaxNode>,
+    /// The parser directives declared by the directive nodes.
+    directives: Directives,
+}
+
+impl SyntaxTree {
+    /// Create an empty syntax tree.
+    pub const fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            directives: Directives::new(),
+        }
+    }
+
+    /// Split the text into the nodes of the syntax tree.
+    ///
+    /// An invalid parser directive is kept as a comment node.
+    pub fn parse(content: &str) -> Self {
+        Self::build(content).0
+    }
+
+    /// Split the text into the nodes of the syntax tree, returning the error of an invalid parser
+    /// directive.
+    pub(crate) fn build(content: &str) -> (Self, Option<ParseError>) {
+        // keep track of the byte offsets and the line break of each line
+        let mut offset = 0;
+        let mut lines = Vec::new();
+        let mut breaks = Vec::new();
+        for chunk in content.split_inclusive('\n') {
+            let line = chunk.strip_suffix('\n').unwrap_or(chunk);
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            lines.push(line.to_owned());
+            breaks.push((offset, offset + line.len(), &chunk[line.len()..]));
+            offset += chunk.len();
+        }
+
+        let (directives, consumed, error) = parse_directives(&lines);
+        let node = |kind, first: usize, last: usize, line: String, heredocs: Vec<Heredoc>| {
+            let (start, _, _) = breaks[first];
+            let (_, end, newline) = breaks[last];
+            SyntaxNode {
+                kind,
+                text: content[start..end].to_owned(),
+                newline: newline.to_owned(),
+                span: Span {
+                    start_line: first + 1,
+                    end_line: last + 1,
+                    start,
+                    end,
+                },
+                content: line,
+                heredocs,
+            }
+        };
+
+        let mut nodes: Vec<SyntaxNode> = lines[..consumed]
+            .iter()
+            .enumerate()
+            .map(|(index, line)| node(NodeKind::Directive, index, index, line.clone(), Vec::new()))
+            .collect();
+
+        let escape = directives.escape_char();
+        for line in process_dockerfile_content(lines.into_iter().skip(consumed), escape) {
+            let kind = if line.content.is_empty() {
+                NodeKind::Empty
+            } else if line.content.starts_with(HASHTAG) {
+                NodeKind::Comment
+            } else {
+                NodeKind::Instruction
+            };
+            nodes.push(node(
+                kind,
+                consumed + line.first,
+                consumed + line.last,
+                line.content,
+                line.heredocs,
+            ));
+        }
+
+        (Self { nodes, directives }, error)
+    }
+
+    /// Return the nodes of the syntax tree in the order of the original text.
+    pub fn nodes(&self) -> &[SyntaxNode] {
+        &self.nodes
+    }
+
+    /// Return the line break used by the text, as found after its first line, or `\n` if the text
+    /// has a single line.
+    pub fn newline(&self) -> &str {
+        self.nodes
+            .iter()
+            .map(|node| node.newline.as_str())
+            .find(|newline| !newline.is_empty())
+            .unwrap_or("\n")
+    }
+
+    /// Return the parser directives declared at the top of the text.
+    pub const fn directives(&self) -> &Directives {
+        &self.directives
+    }
+}
+
+impl fmt::Display for SyntaxTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for node in &self.nodes {
+            write!(f, "{}{}", node.text, node.newline)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_syntax_tree_nodes() {
+        let mut content = String::new();
+        content.push_str("# syntax=docker/dockerfile:1\n");
+        content.push_str("  \n");
+        content.push_str("# Install the tools\n");
+        content.push_str("RUN dnf install -y \\\n");
+        content.push_str("    # the compiler\n");
+        content.push_str("    gcc");
+
+        let tree = SyntaxTree::parse(&content);
+        let kinds: Vec<NodeKind> = tree.nodes().iter().map(|node| node.kind).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                NodeKind::Directive,
+                NodeKind::Empty,
+                NodeKind::Comment,
+                NodeKind::Instruction,
+            ]
+        );
+        assert_eq!(
+            tree.nodes()[3].text,
+            "RUN dnf install -y \\\n    # the compiler\n    gcc"
+        );
+        assert_eq!(tree.nodes()[3].newline, "");
+        assert_eq!(tree.nodes()[3].content(), "RUN dnf install -y gcc");
+        assert_eq!(
+            tree.directives().syntax,
+            Some(String::from("docker/dockerfile:1"))
+        );
+    }
+
+    #[test]
+    fn test_syntax_tree_is_lossless() {
+        let mut content = String::new();
+        content.push_str("# escape=`\r\n");
+        content.push_str("FROM   mcr.microsoft.com/windows/servercore:ltsc2022\r\n");
+        content.push_str("\tENV A=1 `\r\n");
+        content.push_str("    B=\"two words\"\r\n");
+        content.push_str("RUN <<-EOF\n");
+        content.push_str("  \techo hello `\n");
+        content.push_str("\tEOF\n");
+        content.push_str("CMD echo done `\n");
+
+        let tree = SyntaxTree::parse(&content);
+
+        assert_eq!(tree.nodes().len(), 5);
+        assert_eq!(tree.nodes()[3].heredocs()[0].body, "  \techo hello `\n");
+        assert_eq!(tree.newline(), "\r\n");
+        assert_eq!(tree.to_string(), content);
+    }
+
+    #[test]
+    fn test_syntax_tree_keeps_invalid_directive() {
+        let (tree, error) = SyntaxTree::build("# escape=/\nFROM scratch\n");
+
+        assert!(error.is_some());
+        assert_eq!(tree.nodes()[0].kind, NodeKind::Comment);
+    }
+}
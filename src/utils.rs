@@ -3,127 +3,428 @@ use std::sync::LazyLock;
 use regex::Regex;
 
 use crate::ParseResult;
+use crate::ast::Heredoc;
+use crate::error::ErrorDetails;
 use crate::error::ParseError;
-use crate::symbols::chars::BACKSLASH;
+use crate::symbols::chars::DOUBLE_QUOTE;
 use crate::symbols::chars::HASHTAG;
+use crate::symbols::chars::LESS_THAN;
+use crate::symbols::chars::SINGLE_QUOTE;
 use crate::symbols::chars::SPACE;
-use crate::symbols::strings::HEREDOC_END;
-use crate::symbols::strings::HEREDOC_NEWLINE;
-use crate::symbols::strings::HEREDOC_START;
+use crate::symbols::chars::TAB;
+use crate::symbols::strings::HYPHEN_MINUS;
 
-pub fn process_dockerfile_content<I>(lines_iter: I) -> Vec<String>
+/// A logical line joined from one or more physical lines.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LogicalLine {
+    pub content: String,
+    /// The here-documents following the line, in the order they were opened.
+    pub heredocs: Vec<Heredoc>,
+    /// The index of the first physical line.
+    pub first: usize,
+    /// The index of the last physical line.
+    pub last: usize,
+}
+
+pub fn process_dockerfile_content<I>(lines_iter: I, escape: char) -> Vec<LogicalLine>
 where
     I: Iterator<Item = String>,
 {
     let mut result = Vec::new();
 
     let mut current_line = String::new();
-    let mut in_heredoc = false;
+    let mut heredocs: Vec<Heredoc> = Vec::new();
+    // the index of the here-document whose body is being read
+    let mut reading = 0;
+    let mut first = 0;
+    let mut line_count = 0;
 
-    for line in lines_iter {
-        let trimmed_line = line.trim();
+    for (index, line) in lines_iter.enumerate() {
+        line_count = index + 1;
 
-        // skip inline comments
-        if trimmed_line.starts_with(HASHTAG) && !current_line.is_empty() {
-            continue;
-        }
+        if let Some(heredoc) = heredocs.get_mut(reading) {
+            let delimiter = if heredoc.strip_tabs {
+                line.trim_start_matches(TAB)
+            } else {
+                &line
+            };
 
-        if trimmed_line.contains(HEREDOC_START) {
-            in_heredoc = true;
-            current_line.push_str(trimmed_line);
-            add_heredoc_newline(&mut current_line);
+            if delimiter == heredoc.delimiter {
+                reading += 1;
+            } else {
+                heredoc.body.push_str(&line);
+                heredoc.body.push('\n');
+            }
+
+            if reading == heredocs.len() {
+                result.push(LogicalLine {
+                    content: std::mem::take(&mut current_line),
+                    heredocs: std::mem::take(&mut heredocs),
+                    first,
+                    last: index,
+                });
+                reading = 0;
+            }
             continue;
         }
 
-        if in_heredoc && trimmed_line == HEREDOC_END {
-            current_line.push_str(HEREDOC_END);
-            result.push(current_line);
-            current_line = String::new();
-            in_heredoc = false;
-            continue;
+        let trimmed_line = line.trim();
+
+        if current_line.is_empty() {
+            first = index;
         }
 
-        if in_heredoc {
-            current_line.push_str(trimmed_line);
-            add_heredoc_newline(&mut current_line);
+        // skip inline comments
+        if trimmed_line.starts_with(HASHTAG) && !current_line.is_empty() {
             continue;
         }
 
-        if trimmed_line.ends_with(BACKSLASH) {
-            current_line.push_str(&trimmed_line[..trimmed_line.len() - 1]);
+        // whitespace around a line continuation separates words only once
+        if !current_line.is_empty()
+            && !current_line.ends_with(SPACE)
+            && line.starts_with(char::is_whitespace)
+        {
             current_line.push(SPACE);
-        } else {
-            current_line.push_str(trimmed_line);
-            result.push(current_line);
-            current_line = String::new();
         }
-    }
-    result
-}
-
-pub fn split_heredoc(strings: Vec<String>) -> Vec<Vec<String>> {
-    let mut result: Vec<Vec<String>> = Vec::new();
-    let mut current_sub_vector: Vec<String> = Vec::new();
 
-    for s in strings {
-        if s == HEREDOC_NEWLINE {
-            if !current_sub_vector.is_empty() {
-                result.push(current_sub_vector);
+        if let Some(content) = trimmed_line.strip_suffix(escape) {
+            current_line.push_str(content.trim_end());
+            if content.ends_with(char::is_whitespace) {
+                current_line.push(SPACE);
             }
-            current_sub_vector = Vec::new();
         } else {
-            current_sub_vector.push(s);
+            current_line.push_str(trimmed_line);
+
+            // the bodies of here-documents follow the line that opens them
+            heredocs = split_heredocs(&current_line);
+            if heredocs.is_empty() {
+                result.push(LogicalLine {
+                    content: std::mem::take(&mut current_line),
+                    heredocs: Vec::new(),
+                    first,
+                    last: index,
+                });
+            }
         }
     }
 
-    if !current_sub_vector.is_empty() {
-        result.push(current_sub_vector);
+    // keep an unterminated continuation or here-document at the end of the content
+    if !current_line.is_empty() {
+        result.push(LogicalLine {
+            content: current_line.trim_end().to_owned(),
+            heredocs,
+            first,
+            last: line_count.saturating_sub(1),
+        });
     }
-
     result
 }
 
-fn add_heredoc_newline(string: &mut String) {
-    string.push(SPACE);
-    string.push_str(HEREDOC_NEWLINE);
-    string.push(SPACE);
+// https://docs.docker.com/reference/dockerfile/#here-documents
+const HEREDOC_INSTRUCTIONS: [&str; 3] = ["ADD", "COPY", "RUN"];
+
+/// Return the here-documents opened on the line, with empty bodies.
+///
+/// Only `RUN`, `COPY` and `ADD` take here-documents, also as the trigger of `ONBUILD`. A
+/// here-string (`<<<`) does not open a here-document.
+pub fn split_heredocs(line: &str) -> Vec<Heredoc> {
+    static RE: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r#"<<(?P<strip>-)?(?:"(?P<double>[^"\s]+)"|'(?P<single>[^'\s]+)'|(?P<word>[A-Za-z_][A-Za-z0-9_]*))"#)
+            .unwrap()
+    });
+
+    let mut words = line.split_whitespace();
+    let mut keyword = words.next().unwrap_or_default();
+    if keyword.eq_ignore_ascii_case("ONBUILD") {
+        keyword = words.next().unwrap_or_default();
+    }
+    if !HEREDOC_INSTRUCTIONS
+        .iter()
+        .any(|instruction| keyword.eq_ignore_ascii_case(instruction))
+    {
+        return Vec::new();
+    }
+
+    RE.captures_iter(line)
+        .filter(|captures| {
+            let start = captures.get(0).map_or(0, |m| m.start());
+            !line[..start].ends_with(LESS_THAN)
+        })
+        .filter_map(|captures| {
+            let (delimiter, quote) = if let Some(word) = captures.name("double") {
+                (word, Some(DOUBLE_QUOTE))
+            } else if let Some(word) = captures.name("single") {
+                (word, Some(SINGLE_QUOTE))
+            } else {
+                (captures.name("word")?, None)
+            };
+            Some(Heredoc {
+                delimiter: delimiter.as_str().to_owned(),
+                strip_tabs: captures.name("strip").is_some(),
+                quote,
+                body: String::new(),
+            })
+        })
+        .collect()
 }
 
-pub fn split_instruction_and_arguments(line: &str) -> ParseResult<(String, Vec<String>)> {
+/// Split the line into the instruction keyword and its arguments as written.
+pub fn split_instruction_and_arguments(line: &str) -> ParseResult<(String, String)> {
     // https://docs.docker.com/reference/dockerfile/#format
     static RE: LazyLock<Regex> = LazyLock::new(|| {
-        Regex::new(r"^(?P<instruction>[A-Z][A-Z0-9]*)\s+(?P<arguments>\S.+)$").unwrap()
+        Regex::new(r"^(?P<instruction>[A-Za-z][A-Za-z0-9]*)\s+(?P<arguments>\S.*)$").unwrap()
     });
 
-    let captures = RE
-        .captures(line)
-        .ok_or_else(|| ParseError::SyntaxError(line.to_owned()))?;
+    let error = || {
+        ParseError::SyntaxError(Box::new(ErrorDetails::expected(
+            "an instruction followed by arguments",
+            line,
+        )))
+    };
 
-    let instruction = captures
-        .name("instruction")
-        .ok_or_else(|| ParseError::SyntaxError(line.to_owned()))?
-        .as_str();
+    let captures = RE.captures(line).ok_or_else(error)?;
+    let instruction = captures.name("instruction").ok_or_else(error)?.as_str();
+    let arguments = captures.name("arguments").ok_or_else(error)?.as_str();
+
+    Ok((instruction.to_owned(), arguments.to_owned()))
+}
+
+/// Return the keywords of the instruction on the given line, spelled as in the original text.
+///
+/// Besides the instruction itself, this includes the trigger of `ONBUILD`, the `AS` keyword of
+/// `FROM` and the `CMD` or `NONE` keyword of `HEALTHCHECK`.
+pub fn split_keywords(line: &str) -> Vec<String> {
+    let mut words = line.split_whitespace();
+    let Some(keyword) = words.next() else {
+        return Vec::new();
+    };
+
+    let mut keywords = vec![keyword.to_owned()];
+    let mut options = words.skip_while(|word| word.starts_with(HYPHEN_MINUS));
+
+    match keyword.to_uppercase().as_str() {
+        "ONBUILD" => {
+            let trigger = line.trim_start()[keyword.len()..].trim_start();
+            keywords.extend(split_keywords(trigger));
+        }
+        "FROM" => keywords.extend(options.nth(1).map(String::from)),
+        "HEALTHCHECK" => keywords.extend(options.next().map(String::from)),
+        _ => {}
+    }
+    keywords
+}
 
-    let arguments = captures
-        .name("arguments")
-        .ok_or_else(|| ParseError::SyntaxError(line.to_owned()))?
-        .as_str();
+/// Spell the keywords in the formatted line the way they were written in the original text.
+pub fn restore_keywords(line: &str, keywords: &[String]) -> String {
+    let mut result = line.to_owned();
+    let mut position = 0;
 
-    Ok((
-        instruction.to_owned(),
-        // preserve tabs inside heredocs
-        arguments
+    for keyword in keywords {
+        let Some(offset) = result[position..]
             .split(SPACE)
-            .filter(|s| !s.is_empty())
-            .map(String::from)
-            .collect(),
-    ))
+            .scan(position, |start, word| {
+                let current = *start;
+                *start += word.len() + 1;
+                Some((current, word))
+            })
+            .find(|(_, word)| word.eq_ignore_ascii_case(keyword))
+            .map(|(start, _)| start)
+        else {
+            break;
+        };
+
+        result.replace_range(offset..offset + keyword.len(), keyword);
+        position = offset + keyword.len();
+    }
+    result
+}
+
+/// Match the current items with the original ones, keeping their order, and return the index of
+/// the original item matched with each current item.
+///
+/// The longest sequence of equal items is matched first. The items left between two matches are
+/// paired in order when there are as many on both sides, as items modified in place. The others
+/// were inserted or removed.
+pub fn align<A, B>(
+    current: &[A],
+    original: &[B],
+    equal: impl Fn(&A, &B) -> bool,
+) -> Vec<Option<usize>> {
+    let mut matches = vec![None; current.len()];
+
+    // most edits leave a common prefix and suffix
+    let prefix = current
+        .iter()
+        .zip(original)
+        .take_while(|(a, b)| equal(a, b))
+        .count();
+    let suffix = current[prefix..]
+        .iter()
+        .rev()
+        .zip(original[prefix..].iter().rev())
+        .take_while(|(a, b)| equal(a, b))
+        .count();
+    for (offset, item) in matches.iter_mut().take(prefix).enumerate() {
+        *item = Some(offset);
+    }
+    for offset in 1..=suffix {
+        matches[current.len() - offset] = Some(original.len() - offset);
+    }
+
+    let a = &current[prefix..current.len() - suffix];
+    let b = &original[prefix..original.len() - suffix];
+    // the length of the longest common subsequence of a[i..] and b[j..]
+    let mut lengths = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if equal(&a[i], &b[j]) {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut pair = |a_start: usize, a_end: usize, b_start: usize, b_end: usize| {
+        if a_end - a_start == b_end - b_start {
+            for offset in 0..a_end - a_start {
+                matches[prefix + a_start + offset] = Some(prefix + b_start + offset);
+            }
+        }
+    };
+    let (mut i, mut j) = (0, 0);
+    let (mut gap_i, mut gap_j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if equal(&a[i], &b[j]) {
+            pair(gap_i, i, gap_j, j);
+            pair(i, i + 1, j, j + 1);
+            i += 1;
+            j += 1;
+            (gap_i, gap_j) = (i, j);
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pair(gap_i, a.len(), gap_j, b.len());
+    matches
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_process_dockerfile_content_with_backslash() {
+        let lines = ["RUN echo \\", "    hello", "FROM scratch"];
+        let result = process_dockerfile_content(lines.into_iter().map(String::from), '\\');
+
+        assert_eq!(
+            result,
+            vec![
+                LogicalLine {
+                    content: String::from("RUN echo hello"),
+                    heredocs: Vec::new(),
+                    first: 0,
+                    last: 1,
+                },
+                LogicalLine {
+                    content: String::from("FROM scratch"),
+                    heredocs: Vec::new(),
+                    first: 2,
+                    last: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_process_dockerfile_content_with_backtick() {
+        let lines = ["COPY C:\\src\\ `", "    C:\\app\\"];
+        let result = process_dockerfile_content(lines.into_iter().map(String::from), '`');
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].content, "COPY C:\\src\\ C:\\app\\");
+    }
+
+    #[test]
+    fn test_process_dockerfile_content_with_heredoc() {
+        let lines = ["RUN <<EOF", "  dnf upgrade -y", "EOF", "", "USER 1001"];
+        let result = process_dockerfile_content(lines.into_iter().map(String::from), '\\');
+
+        let ranges: Vec<(usize, usize)> = result.iter().map(|l| (l.first, l.last)).collect();
+        assert_eq!(ranges, vec![(0, 2), (3, 3), (4, 4)]);
+        assert_eq!(result[0].content, "RUN <<EOF");
+        assert_eq!(result[0].heredocs[0].body, "  dnf upgrade -y\n");
+    }
+
+    #[test]
+    fn test_process_dockerfile_content_with_multiple_heredocs() {
+        let lines = [
+            "RUN cat <<A > /a && \\",
+            "    cat <<-\"B\" > /b",
+            "# not a comment",
+            "A",
+            "\tB \\",
+            "\tB",
+            "CMD [\"sh\"]",
+        ];
+        let result = process_dockerfile_content(lines.into_iter().map(String::from), '\\');
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].content, "RUN cat <<A > /a && cat <<-\"B\" > /b");
+        assert_eq!((result[0].first, result[0].last), (0, 5));
+        assert_eq!(result[0].heredocs[0].body, "# not a comment\n");
+        assert_eq!(result[0].heredocs[1].body, "\tB \\\n");
+    }
+
+    #[test]
+    fn test_process_dockerfile_content_with_unterminated_heredoc() {
+        let lines = ["RUN <<EOF", "echo hello"];
+        let result = process_dockerfile_content(lines.into_iter().map(String::from), '\\');
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].heredocs[0].body, "echo hello\n");
+        assert_eq!((result[0].first, result[0].last), (0, 1));
+    }
+
+    #[test]
+    fn test_split_heredocs() {
+        let heredocs = split_heredocs("RUN <<EOF <<-EOT <<'END' <<\"NAME\" cat <<<word");
+        let parsed: Vec<(&str, bool, Option<char>)> = heredocs
+            .iter()
+            .map(|h| (h.delimiter.as_str(), h.strip_tabs, h.quote))
+            .collect();
+
+        assert_eq!(
+            parsed,
+            vec![
+                ("EOF", false, None),
+                ("EOT", true, None),
+                ("END", false, Some(SINGLE_QUOTE)),
+                ("NAME", false, Some(DOUBLE_QUOTE)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_heredocs_of_other_instructions() {
+        assert_eq!(split_heredocs("onbuild run <<EOF").len(), 1);
+        assert_eq!(split_heredocs("COPY <<A <<B /app/").len(), 2);
+        assert!(split_heredocs("LABEL description=<<EOF").is_empty());
+    }
+
+    #[test]
+    fn test_process_dockerfile_content_with_unterminated_continuation() {
+        let lines = ["FROM scratch", "CMD echo \\"];
+        let result = process_dockerfile_content(lines.into_iter().map(String::from), '\\');
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[1].content, "CMD echo");
+        assert_eq!((result[1].first, result[1].last), (1, 1));
+    }
+
     #[test]
     fn test_split_empty_line_fails() {
         let line = "";
@@ -132,10 +433,36 @@ mod tests {
     }
 
     #[test]
-    fn test_split_lowercase_instruction_fails() {
+    fn test_split_lowercase_instruction() {
         let line = "run arg1 arg2";
         let result = split_instruction_and_arguments(line);
-        assert!(result.is_err());
+        assert!(result.is_ok());
+
+        let (instruction, arguments) = result.unwrap();
+        assert_eq!(instruction, "run");
+        assert_eq!(arguments, "arg1 arg2");
+    }
+
+    #[test]
+    fn test_split_keywords() {
+        assert_eq!(split_keywords("run make"), vec!["run"]);
+        assert_eq!(
+            split_keywords("From --platform=linux/amd64 fedora as builder"),
+            vec!["From", "as"]
+        );
+        assert_eq!(
+            split_keywords("onbuild healthcheck --retries=3 cmd true"),
+            vec!["onbuild", "healthcheck", "cmd"]
+        );
+    }
+
+    #[test]
+    fn test_restore_keywords() {
+        let keywords = vec![String::from("from"), String::from("as")];
+        let line = "FROM docker.io/library/fedora:latest AS builder";
+
+        let expected = "from docker.io/library/fedora:latest as builder";
+        assert_eq!(restore_keywords(line, &keywords), expected);
     }
 
     #[test]
@@ -147,36 +474,35 @@ mod tests {
 
     #[test]
     fn test_split_instruction_and_arguments() {
-        let line = "RUN arg1 arg2";
+        let line = "RUN arg1  \"arg2\"";
         let result = split_instruction_and_arguments(line);
         assert!(result.is_ok());
 
         let (instruction, arguments) = result.unwrap();
         assert_eq!(instruction, "RUN");
-        assert_eq!(arguments, vec!["arg1", "arg2"]);
-    }
-
-    #[test]
-    fn test_add_heredoc_newline() {
-        let mut string = String::from("test");
-        add_heredoc_newline(&mut string);
-        assert_eq!(string, format!("test {} ", HEREDOC_NEWLINE));
+        assert_eq!(arguments, "arg1  \"arg2\"");
     }
 
     #[test]
-    fn test_split_heredoc() {
-        let strings = vec![
-            String::from("test1"),
-            String::from(HEREDOC_NEWLINE),
-            String::from("test2"),
-            String::from(HEREDOC_NEWLINE),
-            String::from("test3"),
-        ];
-        let result = split_heredoc(strings);
+    fn test_align() {
+        let original = ["FROM", "RUN", "USER", "CMD"];
+        let align = |current: &[&str]| align(current, &original, |a, b| a == b);
 
-        assert_eq!(result.len(), 3);
-        assert_eq!(result[0], vec![String::from("test1")]);
-        assert_eq!(result[1], vec![String::from("test2")]);
-        assert_eq!(result[2], vec![String::from("test3")]);
+        assert_eq!(
+            align(&["FROM", "RUN", "USER", "CMD"]),
+            vec![Some(0), Some(1), Some(2), Some(3)]
+        );
+        assert_eq!(
+            align(&["#", "FROM", "USER", "CMD"]),
+            vec![None, Some(0), Some(2), Some(3)]
+        );
+        assert_eq!(
+            align(&["FROM", "ENV", "USER", "#", "CMD"]),
+            vec![Some(0), Some(1), Some(2), None, Some(3)]
+        );
+        assert_eq!(
+            align(&["CMD", "FROM", "RUN", "USER"]),
+            vec![None, Some(0), Some(1), Some(2)]
+        );
     }
 }
@@ -0,0 +1,225 @@
+use std::fmt;
+use std::ops::Deref;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::symbols::chars::DOLLAR;
+use crate::symbols::chars::DOUBLE_QUOTE;
+use crate::symbols::chars::SINGLE_QUOTE;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+/// This struct represents a word of the instruction arguments, with quotes and escapes removed.
+///
+/// The word remembers which of its dollar signs were quoted or escaped. Those stay literal when
+/// the variables of the word are expanded, all the other dollar signs start a substitution.
+///
+/// ### Example
+///
+/// ```
+/// use dockerfile_parser_rs::Word;
+///
+/// let word = Word::from("$HOME/app");
+/// assert_eq!(word, "$HOME/app");
+/// assert!(word.has_variables());
+///
+/// let word = Word::literal("$HOME/app");
+/// assert_eq!(word.written('\\'), "\\$HOME/app");
+/// assert!(!word.has_variables());
+/// ```
+pub struct Word {
+    value: String,
+    /// The byte offsets of the literal dollar signs.
+    literal: Vec<usize>,
+}
+
+impl Word {
+    /// Create a word in which every dollar sign starts a substitution.
+    pub fn new(value: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+            literal: Vec::new(),
+        }
+    }
+
+    /// Create a word in which every dollar sign is literal, e.g. a word that was already expanded.
+    pub fn literal(value: impl Into<String>) -> Self {
+        let value = value.into();
+        let literal = value
+            .match_indices(DOLLAR)
+            .map(|(index, _)| index)
+            .collect();
+        Self { value, literal }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+
+    /// Return whether the dollar sign at the byte offset is literal.
+    pub fn is_literal(&self, index: usize) -> bool {
+        self.literal.binary_search(&index).is_ok()
+    }
+
+    /// Return whether the word has a dollar sign that starts a substitution.
+    pub fn has_variables(&self) -> bool {
+        self.value
+            .match_indices(DOLLAR)
+            .any(|(index, _)| !self.is_literal(index))
+    }
+
+    /// Return the word as it could be written in a Dockerfile.
+    ///
+    /// Quotes, whitespace, the escape character and the literal dollar signs are escaped.
+    pub fn written(&self, escape: char) -> String {
+        let mut result = String::with_capacity(self.value.len());
+        for (index, c) in self.value.char_indices() {
+            let escaped = match c {
+                DOLLAR => self.is_literal(index),
+                SINGLE_QUOTE | DOUBLE_QUOTE => true,
+                c => c == escape || c.is_whitespace(),
+            };
+            if escaped {
+                result.push(escape);
+            }
+            result.push(c);
+        }
+        result
+    }
+
+    /// Append a character, a dollar sign is appended as literal if `literal` is set.
+    pub fn push(&mut self, c: char, literal: bool) {
+        if c == DOLLAR && literal {
+            self.literal.push(self.value.len());
+        }
+        self.value.push(c);
+    }
+
+    /// Append another word, keeping its literal dollar signs.
+    pub fn push_word(&mut self, word: &Self) {
+        let offset = self.value.len();
+        self.literal
+            .extend(word.literal.iter().map(|index| index + offset));
+        self.value.push_str(&word.value);
+    }
+
+    /// Return the part of the word between the byte offsets.
+    fn slice(&self, start: usize, end: usize) -> Self {
+        let literal = self
+            .literal
+            .iter()
+            .filter(|index| (start..end).contains(*index))
+            .map(|index| index - start)
+            .collect();
+        Self {
+            value: self.value[start..end].to_owned(),
+            literal,
+        }
+    }
+
+    /// Split the word around the first occurrence of the delimiter.
+    pub fn split_once(&self, delimiter: char) -> Option<(Self, Self)> {
+        let index = self.value.find(delimiter)?;
+        Some((
+            self.slice(0, index),
+            self.slice(index + delimiter.len_utf8(), self.value.len()),
+        ))
+    }
+}
+
+impl Deref for Word {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl AsRef<str> for Word {
+    fn as_ref(&self) -> &str {
+        &self.value
+    }
+}
+
+impl From<&str> for Word {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<String> for Word {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<Word> for String {
+    fn from(word: Word) -> Self {
+        word.value
+    }
+}
+
+impl PartialEq<str> for Word {
+    fn eq(&self, other: &str) -> bool {
+        self.value == other
+    }
+}
+
+impl PartialEq<&str> for Word {
+    fn eq(&self, other: &&str) -> bool {
+        self.value == *other
+    }
+}
+
+impl PartialEq<String> for Word {
+    fn eq(&self, other: &String) -> bool {
+        &self.value == other
+    }
+}
+
+impl fmt::Display for Word {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbols::chars::BACKSLASH;
+    use crate::symbols::chars::BACKTICK;
+
+    #[test]
+    fn test_word_literal_dollar_signs() {
+        let mut word = Word::from("$A");
+        word.push_word(&Word::literal("$B"));
+        word.push(DOLLAR, false);
+
+        assert_eq!(word, "$A$B$");
+        assert!(!word.is_literal(0));
+        assert!(word.is_literal(2));
+        assert!(!word.is_literal(4));
+        assert!(word.has_variables());
+        assert!(!word.slice(2, 4).has_variables());
+    }
+
+    #[test]
+    fn test_word_written() {
+        let mut word = Word::from("it's \"C:\\$A\" ");
+        word.push_word(&Word::literal("$B"));
+
+        assert_eq!(word.written(BACKSLASH), "it\\'s\\ \\\"C:\\\\$A\\\"\\ \\$B");
+        assert_eq!(word.written(BACKTICK), "it`'s` `\"C:\\$A`\"` `$B");
+    }
+
+    #[test]
+    fn test_word_split_once() {
+        let word = Word::literal("key=$value");
+        let (key, value) = word.split_once('=').unwrap();
+
+        assert_eq!(key, "key");
+        assert_eq!(value, Word::literal("$value"));
+        assert!(Word::from("key").split_once('=').is_none());
+    }
+}
@@ -0,0 +1,115 @@
+// https://docs.docker.com/reference/dockerfile/#workdir
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::error::ErrorDetails;
+use crate::error::ParseError;
+use crate::symbols::chars::BACKSLASH;
+use crate::symbols::chars::COLON;
+use crate::symbols::chars::DOLLAR;
+use crate::symbols::chars::SLASH;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// This enum represents the path of the WORKDIR instruction.
+///
+/// A relative path is resolved against the previous working directory of the stage.
+///
+/// ### Example
+///
+/// ```
+/// use dockerfile_parser_rs::WorkdirPath;
+///
+/// let path: WorkdirPath = "/app".parse().unwrap();
+/// assert!(path.is_absolute());
+///
+/// let path: WorkdirPath = "src".parse().unwrap();
+/// assert_eq!(path, WorkdirPath::Relative(String::from("src")));
+/// ```
+pub enum WorkdirPath {
+    /// An absolute path, e.g. `/app` or `C:\app`.
+    Absolute(String),
+    Relative(String),
+    /// A path with variables that are not expanded yet, as written.
+    Variable(String),
+}
+
+impl WorkdirPath {
+    pub const fn is_absolute(&self) -> bool {
+        matches!(self, Self::Absolute(_))
+    }
+
+    /// Return the path as written.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Absolute(path) | Self::Relative(path) | Self::Variable(path) => path,
+        }
+    }
+}
+
+impl FromStr for WorkdirPath {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(ParseError::MissingArgument(Box::new(ErrorDetails::new(
+                "WORKDIR requires a non-empty path",
+            ))));
+        }
+        if s.contains(DOLLAR) {
+            return Ok(Self::Variable(s.to_owned()));
+        }
+
+        // a Windows path starts with a drive letter, e.g. C:\ or C:/
+        let mut chars = s.chars();
+        let windows = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+            && chars.next() == Some(COLON)
+            && chars.next().is_some_and(|c| c == SLASH || c == BACKSLASH);
+
+        if s.starts_with([SLASH, BACKSLASH]) || windows {
+            Ok(Self::Absolute(s.to_owned()))
+        } else {
+            Ok(Self::Relative(s.to_owned()))
+        }
+    }
+}
+
+impl fmt::Display for WorkdirPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workdir_path_kinds() {
+        let cases = [
+            ("/app", WorkdirPath::Absolute(String::from("/app"))),
+            ("C:\\app", WorkdirPath::Absolute(String::from("C:\\app"))),
+            ("c:/app", WorkdirPath::Absolute(String::from("c:/app"))),
+            ("app/src", WorkdirPath::Relative(String::from("app/src"))),
+            ("C:app", WorkdirPath::Relative(String::from("C:app"))),
+            (
+                "$HOME/app",
+                WorkdirPath::Variable(String::from("$HOME/app")),
+            ),
+        ];
+
+        for (path, expected) in cases {
+            assert_eq!(path.parse::<WorkdirPath>().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_workdir_path_empty() {
+        let result = "".parse::<WorkdirPath>();
+
+        assert!(matches!(result, Err(ParseError::MissingArgument(_))));
+    }
+}
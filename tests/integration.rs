@@ -1,7 +1,12 @@
 use std::io::Write;
 
+use dockerfile_parser_rs::Command;
 use dockerfile_parser_rs::Dockerfile;
+use dockerfile_parser_rs::Heredoc;
+use dockerfile_parser_rs::Instruction;
 use dockerfile_parser_rs::ParseError;
+use dockerfile_parser_rs::Source;
+use dockerfile_parser_rs::Word;
 
 #[test]
 fn test_parse() {
@@ -21,6 +26,64 @@ fn test_parse_and_dump() {
     let dumped_content = std::fs::read_to_string(temp_file).unwrap();
     assert_eq!(original_content, dumped_content);
 }
+
+#[test]
+fn test_parse_and_dump_is_lossless() {
+    let path = std::path::PathBuf::from("tests/dockerfiles/formatted/Dockerfile");
+    let mut dockerfile = Dockerfile::from(path.clone()).unwrap();
+
+    let temp_file = std::env::temp_dir().join("Dockerfile.formatted");
+    dockerfile.dump(temp_file.clone()).unwrap();
+
+    let original_content = std::fs::read_to_string(path).unwrap();
+    let dumped_content = std::fs::read_to_string(temp_file.clone()).unwrap();
+    assert_eq!(original_content, dumped_content);
+    assert_eq!(dockerfile.syntax_tree().to_string(), original_content);
+
+    // only the modified instruction is formatted
+    dockerfile.instructions[6] = Instruction::Cmd(Command::Shell(String::from("make test")));
+    dockerfile.dump(temp_file.clone()).unwrap();
+
+    let dumped_content = std::fs::read_to_string(temp_file).unwrap();
+    let expected = original_content.replace("CMD make run\r\n", "CMD make test\r\n");
+    assert_eq!(dumped_content, expected);
+}
+
+#[test]
+fn test_parse_and_dump_with_original_case() {
+    let path = std::path::PathBuf::from("tests/dockerfiles/lowercase/Dockerfile");
+    let dockerfile = Dockerfile::from(path.clone()).unwrap();
+    assert_eq!(dockerfile.stages().len(), 2);
+
+    let temp_file = std::env::temp_dir().join("Dockerfile.lowercase");
+    dockerfile
+        .dump_with_original_case(temp_file.clone())
+        .unwrap();
+
+    let original_content = std::fs::read_to_string(path).unwrap();
+    let dumped_content = std::fs::read_to_string(temp_file).unwrap();
+    assert_eq!(original_content, dumped_content);
+}
+
+#[test]
+fn test_parse_and_dump_with_escape_directive() {
+    let path = std::path::PathBuf::from("tests/dockerfiles/windows/Dockerfile");
+    let dockerfile = Dockerfile::from(path).unwrap();
+
+    assert_eq!(dockerfile.directives.escape, Some('`'));
+    assert!(matches!(
+        &dockerfile.instructions[6],
+        Instruction::Run { command: Command::Shell(command), .. } if command == "dir C:\\ && echo done"
+    ));
+
+    let temp_file = std::env::temp_dir().join("Dockerfile.windows");
+    dockerfile.dump(temp_file.clone()).unwrap();
+
+    let dumped_content = std::fs::read_to_string(temp_file).unwrap();
+    assert!(dumped_content.starts_with("# escape=`\n"));
+    assert!(dumped_content.contains("LABEL description=\"Build `\"tools`\" image\""));
+}
+
 #[test]
 fn test_invalid_instruction_name() {
     let temp_file = std::env::temp_dir().join("Dockerfile.temp");
@@ -31,3 +94,92 @@ fn test_invalid_instruction_name() {
     let result = Dockerfile::from(temp_file);
     assert!(matches!(result, Err(ParseError::UnknownInstruction(_))));
 }
+
+#[test]
+fn test_parse_tolerant_and_dump() {
+    let content = "FROM scratch\nMAKE love\nUSER 1001\nCOPY\n";
+    let (dockerfile, errors) = Dockerfile::parse_tolerant(content);
+
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(errors[0], ParseError::UnknownInstruction(_)));
+    assert!(matches!(errors[1], ParseError::SyntaxError(_)));
+
+    let temp_file = std::env::temp_dir().join("Dockerfile.tolerant");
+    dockerfile.dump(temp_file.clone()).unwrap();
+
+    let dumped_content = std::fs::read_to_string(temp_file).unwrap();
+    assert_eq!(dumped_content, content);
+}
+
+#[test]
+fn test_parse_quoted_arguments() {
+    let content = "FROM scratch\nCOPY \"my file.txt\" /app/\nLABEL desc=\"two  spaces\" other='single'\nARG MESSAGE=\"hello world\"\n";
+    let dockerfile = content.parse::<Dockerfile>().unwrap();
+
+    assert!(matches!(
+        &dockerfile.instructions[1],
+        Instruction::Copy { sources, .. } if sources == &vec![Source::Path(Word::from("my file.txt"))]
+    ));
+    assert_eq!(
+        dockerfile.instructions[2].to_string(),
+        "LABEL desc=\"two  spaces\" other=\"single\""
+    );
+    assert_eq!(
+        dockerfile.instructions[3],
+        Instruction::Arg(vec![(
+            String::from("MESSAGE"),
+            Some(Word::from("hello world"))
+        )])
+    );
+}
+
+#[test]
+fn test_parse_heredocs() {
+    let path = std::path::PathBuf::from("tests/dockerfiles/heredocs/Dockerfile");
+    let dockerfile = Dockerfile::from(path.clone()).unwrap();
+
+    let heredocs: Vec<&Heredoc> = dockerfile
+        .instructions
+        .iter()
+        .flat_map(|instruction| match instruction {
+            Instruction::Run { heredocs, .. } => heredocs.iter().collect(),
+            Instruction::Add { sources, .. } | Instruction::Copy { sources, .. } => sources
+                .iter()
+                .filter_map(|source| match source {
+                    Source::Heredoc(heredoc) => Some(heredoc),
+                    Source::Path(_) => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        })
+        .collect();
+
+    let delimiters: Vec<(&str, bool, bool)> = heredocs
+        .iter()
+        .map(|h| (h.delimiter.as_str(), h.strip_tabs, h.expands()))
+        .collect();
+    assert_eq!(
+        delimiters,
+        vec![
+            ("END", false, true),
+            ("EOF", false, true),
+            ("PY", false, false),
+            ("EOT", true, true),
+            ("A", false, true),
+            ("B", false, false),
+            ("EOF", false, true),
+            ("A", false, false),
+            ("B", true, true),
+        ]
+    );
+    assert_eq!(heredocs[3].content(), "set -eu\necho indented\n");
+
+    // formatting the instructions prints the here-documents back exactly
+    let formatted: Vec<String> = dockerfile
+        .instructions
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    let original_content = std::fs::read_to_string(path).unwrap();
+    assert_eq!(formatted.join("\n") + "\n", original_content);
+}